@@ -18,6 +18,7 @@ pub mod date;
 pub mod dictionary;
 pub mod error;
 pub mod filedescriptor;
+pub mod mach_port;
 pub mod messageport;
 pub mod number;
 pub mod propertylist;
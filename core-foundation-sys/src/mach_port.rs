@@ -0,0 +1,36 @@
+// Copyright 2018 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::os::raw::c_void;
+
+use base::{CFAllocatorRef, CFIndex, CFTypeID, Boolean};
+use runloop::CFRunLoopSourceRef;
+
+#[repr(C)]
+pub struct __CFMachPort(c_void);
+pub type CFMachPortRef = *mut __CFMachPort;
+
+extern {
+    /*
+     * CFMachPort.h
+     */
+    pub fn CFMachPortGetTypeID() -> CFTypeID;
+    pub fn CFMachPortInvalidate(port: CFMachPortRef);
+    pub fn CFMachPortIsValid(port: CFMachPortRef) -> Boolean;
+    pub fn CFMachPortCreateRunLoopSource(allocator: CFAllocatorRef,
+                                         port: CFMachPortRef,
+                                         order: CFIndex)
+        -> CFRunLoopSourceRef;
+    // CFMachPortCreate
+    // CFMachPortCreateWithPort
+    // CFMachPortGetPort
+    // CFMachPortGetContext
+    // CFMachPortGetInvalidationCallBack
+    // CFMachPortSetInvalidationCallBack
+}
@@ -11,7 +11,8 @@
 
 pub use core_foundation_sys::dictionary::*;
 
-use core_foundation_sys::base::{CFTypeRef, CFRelease, kCFAllocatorDefault};
+use core_foundation_sys::base::{CFTypeRef, CFRelease, CFGetTypeID, kCFAllocatorDefault};
+use core_foundation_sys::number::{CFBooleanGetTypeID, CFBooleanGetValue, CFBooleanRef};
 use std::mem;
 use std::os::raw::c_void;
 use std::ptr;
@@ -89,6 +90,20 @@ impl<K, V> CFDictionary<K, V> {
         self.find(key).expect(&format!("No entry found for key {:p}", ptr))
     }
 
+    /// Looks up a `CFBoolean` value, returning `None` if the key is not present or its value is
+    /// not a `CFBoolean`.
+    pub fn get_bool<T: ToVoid<K>>(&self, key: T) -> Option<bool> where K: ToVoid<K> {
+        unsafe {
+            let mut value: *const c_void = ptr::null();
+            if CFDictionaryGetValueIfPresent(self.0, key.to_void(), &mut value) != 0 &&
+                    CFGetTypeID(value) == CFBooleanGetTypeID() {
+                Some(CFBooleanGetValue(value as CFBooleanRef) != 0)
+            } else {
+                None
+            }
+        }
+    }
+
     pub fn get_keys_and_values(&self) -> (Vec<*const c_void>, Vec<*const c_void>) {
         let length = self.len();
         let mut keys = Vec::with_capacity(length);
@@ -313,4 +328,21 @@ pub mod test {
         assert_eq!(value, CFBoolean::true_value());
         assert_eq!(dict.find(&invalid_key), None);
     }
+
+    #[test]
+    fn dict_get_bool() {
+        let yes = CFString::from_static_string("yes");
+        let no = CFString::from_static_string("no");
+        let number = CFString::from_static_string("number");
+        let dict = CFDictionary::from_CFType_pairs(&[
+            (yes.clone(), CFBoolean::true_value().as_CFType()),
+            (no.clone(), CFBoolean::false_value().as_CFType()),
+            (number.clone(), CFNumber::from(1).as_CFType()),
+        ]);
+
+        assert_eq!(dict.get_bool(&yes), Some(true));
+        assert_eq!(dict.get_bool(&no), Some(false));
+        assert_eq!(dict.get_bool(&number), None);
+        assert_eq!(dict.get_bool(&CFString::from_static_string("missing")), None);
+    }
 }
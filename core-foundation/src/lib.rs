@@ -169,6 +169,7 @@ pub mod date;
 pub mod dictionary;
 pub mod error;
 pub mod filedescriptor;
+pub mod mach_port;
 pub mod number;
 pub mod set;
 pub mod string;
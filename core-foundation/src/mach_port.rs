@@ -0,0 +1,50 @@
+// Copyright 2018 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Mach ports, e.g. the ones backing event taps.
+
+pub use core_foundation_sys::mach_port::*;
+
+use core_foundation_sys::base::{CFIndex, kCFAllocatorDefault};
+
+use base::TCFType;
+use runloop::CFRunLoopSource;
+
+declare_TCFType!{
+    /// A Mach port.
+    CFMachPort, CFMachPortRef
+}
+impl_TCFType!(CFMachPort, CFMachPortRef, CFMachPortGetTypeID);
+
+impl CFMachPort {
+    pub fn valid(&self) -> bool {
+        unsafe {
+            CFMachPortIsValid(self.0) != 0
+        }
+    }
+
+    /// Stops the port from receiving messages and removes its run loop sources from their run
+    /// loops.
+    pub fn invalidate(&self) {
+        unsafe {
+            CFMachPortInvalidate(self.0)
+        }
+    }
+
+    pub fn to_run_loop_source(&self, order: CFIndex) -> Option<CFRunLoopSource> {
+        unsafe {
+            let source_ref = CFMachPortCreateRunLoopSource(kCFAllocatorDefault, self.0, order);
+            if source_ref.is_null() {
+                None
+            } else {
+                Some(TCFType::wrap_under_create_rule(source_ref))
+            }
+        }
+    }
+}
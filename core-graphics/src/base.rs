@@ -13,6 +13,8 @@
 #![allow(non_upper_case_globals)]
 
 use libc;
+use std::error::Error;
+use std::fmt;
 
 #[cfg(any(target_arch = "x86",
           target_arch = "arm",
@@ -26,7 +28,110 @@ pub type CGFloat = libc::c_double;
 #[cfg(not(target_pointer_width = "64"))]
 pub type CGFloat = libc::c_float;
 
-pub type CGError = libc::int32_t;
+/// The raw result code returned by Core Graphics functions.
+pub type CGErrorCode = libc::int32_t;
+
+pub const kCGErrorSuccess: CGErrorCode = 0;
+pub const kCGErrorFailure: CGErrorCode = 1000;
+pub const kCGErrorIllegalArgument: CGErrorCode = 1001;
+pub const kCGErrorInvalidConnection: CGErrorCode = 1002;
+pub const kCGErrorInvalidContext: CGErrorCode = 1003;
+pub const kCGErrorCannotComplete: CGErrorCode = 1004;
+pub const kCGErrorNotImplemented: CGErrorCode = 1006;
+pub const kCGErrorRangeCheck: CGErrorCode = 1007;
+pub const kCGErrorTypeCheck: CGErrorCode = 1008;
+pub const kCGErrorInvalidOperation: CGErrorCode = 1010;
+pub const kCGErrorNoneAvailable: CGErrorCode = 1011;
+
+/// An error returned by a Core Graphics function.
+///
+/// Only functions that report a result code fail with a `CGError`; those that
+/// signal failure by returning NULL, like the event functions, return
+/// `Result<_, ()>` or `Option` instead, as there is no code to report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CGError {
+    Success,
+    Failure,
+    IllegalArgument,
+    InvalidConnection,
+    InvalidContext,
+    CannotComplete,
+    NotImplemented,
+    RangeCheck,
+    TypeCheck,
+    InvalidOperation,
+    NoneAvailable,
+    /// A result code without a named variant.
+    Other(CGErrorCode),
+}
+
+impl CGError {
+    /// Returns the error for a raw result code. Unknown codes are reported as
+    /// `Other`.
+    pub fn from_code(code: CGErrorCode) -> CGError {
+        match code {
+            kCGErrorSuccess => CGError::Success,
+            kCGErrorFailure => CGError::Failure,
+            kCGErrorIllegalArgument => CGError::IllegalArgument,
+            kCGErrorInvalidConnection => CGError::InvalidConnection,
+            kCGErrorInvalidContext => CGError::InvalidContext,
+            kCGErrorCannotComplete => CGError::CannotComplete,
+            kCGErrorNotImplemented => CGError::NotImplemented,
+            kCGErrorRangeCheck => CGError::RangeCheck,
+            kCGErrorTypeCheck => CGError::TypeCheck,
+            kCGErrorInvalidOperation => CGError::InvalidOperation,
+            kCGErrorNoneAvailable => CGError::NoneAvailable,
+            code => CGError::Other(code),
+        }
+    }
+
+    /// Returns the raw result code of the error.
+    pub fn code(&self) -> CGErrorCode {
+        match *self {
+            CGError::Success => kCGErrorSuccess,
+            CGError::Failure => kCGErrorFailure,
+            CGError::IllegalArgument => kCGErrorIllegalArgument,
+            CGError::InvalidConnection => kCGErrorInvalidConnection,
+            CGError::InvalidContext => kCGErrorInvalidContext,
+            CGError::CannotComplete => kCGErrorCannotComplete,
+            CGError::NotImplemented => kCGErrorNotImplemented,
+            CGError::RangeCheck => kCGErrorRangeCheck,
+            CGError::TypeCheck => kCGErrorTypeCheck,
+            CGError::InvalidOperation => kCGErrorInvalidOperation,
+            CGError::NoneAvailable => kCGErrorNoneAvailable,
+            CGError::Other(code) => code,
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match *self {
+            CGError::Success => "success",
+            CGError::Failure => "failure",
+            CGError::IllegalArgument => "illegal argument",
+            CGError::InvalidConnection => "invalid connection to the window server",
+            CGError::InvalidContext => "invalid context",
+            CGError::CannotComplete => "operation cannot be completed",
+            CGError::NotImplemented => "not implemented",
+            CGError::RangeCheck => "value out of range",
+            CGError::TypeCheck => "wrong type",
+            CGError::InvalidOperation => "invalid operation",
+            CGError::NoneAvailable => "none available",
+            CGError::Other(_) => "unknown error",
+        }
+    }
+}
+
+impl fmt::Display for CGError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (CGError {})", self.message(), self.code())
+    }
+}
+
+impl Error for CGError {
+    fn description(&self) -> &str {
+        self.message()
+    }
+}
 
 pub const kCGImageAlphaNone: u32 = 0;
 pub const kCGImageAlphaPremultipliedLast: u32 = 1;
@@ -36,6 +141,10 @@ pub const kCGImageAlphaFirst: u32 = 4;
 pub const kCGImageAlphaNoneSkipLast: u32 = 5;
 pub const kCGImageAlphaNoneSkipFirst: u32 = 6;
 
+pub const kCGBitmapAlphaInfoMask: u32 = 0x1F;
+pub const kCGBitmapFloatComponents: u32 = (1 << 8);
+
+pub const kCGBitmapByteOrderMask: u32 = 0x7000;
 pub const kCGBitmapByteOrderDefault: u32 = (0 << 12);
 pub const kCGBitmapByteOrder16Little: u32 = (1 << 12);
 pub const kCGBitmapByteOrder32Little: u32 = (2 << 12);
@@ -51,3 +160,13 @@ pub const kCGBitmapByteOrder32Host: u32 = kCGBitmapByteOrder32Big;
 pub const kCGBitmapByteOrder16Host: u32 = kCGBitmapByteOrder16Little;
 #[cfg(target_endian = "little")]
 pub const kCGBitmapByteOrder32Host: u32 = kCGBitmapByteOrder32Little;
+
+#[test]
+fn cg_error_code_test() {
+    assert_eq!(CGError::from_code(1001), CGError::IllegalArgument);
+    assert_eq!(CGError::from_code(1001).code(), 1001);
+    assert_eq!(CGError::from_code(1000), CGError::Failure);
+    assert_eq!(CGError::from_code(-1), CGError::Other(-1));
+    assert_eq!(CGError::from_code(-1).code(), -1);
+    assert_eq!(CGError::RangeCheck.to_string(), "value out of range (CGError 1007)");
+}
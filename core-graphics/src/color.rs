@@ -0,0 +1,138 @@
+// Copyright 2018 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use base::CGFloat;
+use color_space::{CGColorRenderingIntent, CGColorSpace, kCGColorSpaceSRGB};
+use core_foundation::base::{CFRelease, CFRetain, CFTypeID};
+use core_foundation::dictionary::CFDictionaryRef;
+use foreign_types::ForeignType;
+use libc::size_t;
+use std::ptr;
+use std::slice;
+
+foreign_type! {
+    #[doc(hidden)]
+    type CType = ::sys::CGColor;
+    fn drop = |p| CFRelease(p as *mut _);
+    fn clone = |p| CFRetain(p as *const _) as *mut _;
+    pub struct CGColor;
+    pub struct CGColorRef;
+}
+
+impl CGColor {
+    pub fn type_id() -> CFTypeID {
+        unsafe {
+            CGColorGetTypeID()
+        }
+    }
+
+    /// Creates a color in `space` from its components, which must include alpha as the last
+    /// component.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `components` doesn't have one value per component of `space` plus alpha.
+    pub fn new(space: &CGColorSpace, components: &[CGFloat]) -> CGColor {
+        assert_eq!(components.len(), space.number_of_components() + 1);
+        unsafe {
+            let result = CGColorCreate(space.as_ptr(), components.as_ptr());
+            assert!(!result.is_null());
+            CGColor::from_ptr(result)
+        }
+    }
+
+    /// Creates a color in the device RGB color space.
+    pub fn rgb(red: CGFloat, green: CGFloat, blue: CGFloat, alpha: CGFloat) -> CGColor {
+        CGColor::new(&CGColorSpace::create_device_rgb(), &[red, green, blue, alpha])
+    }
+
+    /// Creates a color in the sRGB color space.
+    pub fn srgb(red: CGFloat, green: CGFloat, blue: CGFloat, alpha: CGFloat) -> CGColor {
+        let space = unsafe { CGColorSpace::create_with_name(kCGColorSpaceSRGB) };
+        CGColor::new(&space.expect("sRGB color space"), &[red, green, blue, alpha])
+    }
+
+    /// Returns the components of the color in its own color space, followed by alpha.
+    pub fn components(&self) -> Vec<CGFloat> {
+        self.components_slice().to_vec()
+    }
+
+    /// Returns the red component of the color converted to sRGB, or `None` if it can't be
+    /// converted.
+    pub fn red(&self) -> Option<CGFloat> {
+        self.srgb_component(0)
+    }
+
+    /// Returns the green component of the color converted to sRGB, or `None` if it can't be
+    /// converted.
+    pub fn green(&self) -> Option<CGFloat> {
+        self.srgb_component(1)
+    }
+
+    /// Returns the blue component of the color converted to sRGB, or `None` if it can't be
+    /// converted.
+    pub fn blue(&self) -> Option<CGFloat> {
+        self.srgb_component(2)
+    }
+
+    fn components_slice(&self) -> &[CGFloat] {
+        unsafe {
+            let count = CGColorGetNumberOfComponents(self.as_ptr());
+            slice::from_raw_parts(CGColorGetComponents(self.as_ptr()), count)
+        }
+    }
+
+    fn srgb_component(&self, index: usize) -> Option<CGFloat> {
+        unsafe {
+            let space = match CGColorSpace::create_with_name(kCGColorSpaceSRGB) {
+                Some(space) => space,
+                None => return None,
+            };
+            let converted = CGColorCreateCopyByMatchingToColorSpace(
+                space.as_ptr(),
+                CGColorRenderingIntent::Default,
+                self.as_ptr(),
+                ptr::null());
+            if converted.is_null() {
+                return None;
+            }
+            let converted = CGColor::from_ptr(converted);
+            converted.components_slice().get(index).cloned()
+        }
+    }
+}
+
+#[test]
+fn srgb_component_test() {
+    let color = CGColor::srgb(0.25, 0.5, 0.75, 1.0);
+    assert!((color.red().unwrap() - 0.25).abs() < 0.001);
+    assert!((color.green().unwrap() - 0.5).abs() < 0.001);
+    assert!((color.blue().unwrap() - 0.75).abs() < 0.001);
+}
+
+#[test]
+#[should_panic]
+fn new_without_alpha_test() {
+    CGColor::new(&CGColorSpace::create_device_rgb(), &[0.25, 0.5, 0.75]);
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern {
+    fn CGColorGetTypeID() -> CFTypeID;
+    fn CGColorCreate(space: ::sys::CGColorSpaceRef,
+                     components: *const CGFloat)
+                     -> ::sys::CGColorRef;
+    fn CGColorCreateCopyByMatchingToColorSpace(space: ::sys::CGColorSpaceRef,
+                                               intent: CGColorRenderingIntent,
+                                               color: ::sys::CGColorRef,
+                                               options: CFDictionaryRef)
+                                               -> ::sys::CGColorRef;
+    fn CGColorGetNumberOfComponents(color: ::sys::CGColorRef) -> size_t;
+    fn CGColorGetComponents(color: ::sys::CGColorRef) -> *const CGFloat;
+}
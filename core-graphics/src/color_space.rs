@@ -7,9 +7,37 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use core_foundation::base::{CFRelease, CFRetain, CFTypeID};
+use core_foundation::base::{CFEqual, CFRelease, CFRetain, CFTypeID};
 use core_foundation::string::CFStringRef;
-use foreign_types::ForeignType;
+use foreign_types::{ForeignType, ForeignTypeRef};
+use libc::size_t;
+use std::ptr;
+#[cfg(target_os = "macos")]
+use display::CGDisplay;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CGColorRenderingIntent {
+    Default,
+    AbsoluteColorimetric,
+    RelativeColorimetric,
+    Perceptual,
+    Saturation,
+}
+
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CGColorSpaceModel {
+    Unknown = -1,
+    Monochrome,
+    RGB,
+    CMYK,
+    Lab,
+    DeviceN,
+    Indexed,
+    Pattern,
+    XYZ,
+}
 
 foreign_type! {
     #[doc(hidden)]
@@ -34,12 +62,139 @@ impl CGColorSpace {
         }
     }
 
+    /// Creates an sRGB color space.
+    pub fn create_srgb() -> CGColorSpace {
+        unsafe { CGColorSpace::create_with_name(kCGColorSpaceSRGB) }.expect("sRGB color space")
+    }
+
     pub fn create_device_rgb() -> CGColorSpace {
         unsafe {
             let result = CGColorSpaceCreateDeviceRGB();
             CGColorSpace::from_ptr(result)
         }
     }
+
+    /// Creates a gray color space with a linear transfer function, for compositing and masks
+    /// that should blend in linear light. Returns `None` before macOS 10.12.
+    pub fn create_linear_gray() -> Option<CGColorSpace> {
+        unsafe { CGColorSpace::create_with_name(kCGColorSpaceLinearGray) }
+    }
+
+    /// Creates an extended-range gray color space, which allows values outside of [0, 1].
+    /// Returns `None` before macOS 10.12.
+    pub fn create_extended_gray() -> Option<CGColorSpace> {
+        unsafe { CGColorSpace::create_with_name(kCGColorSpaceExtendedGray) }
+    }
+
+    /// Creates a color space for painting with patterns: with no base color space for
+    /// colored patterns, or with the color space of the color uncolored patterns are
+    /// painted with.
+    pub fn create_pattern(base: Option<&CGColorSpace>) -> CGColorSpace {
+        unsafe {
+            let base = base.map_or(ptr::null_mut(), |base| base.as_ptr());
+            let result = CGColorSpaceCreatePattern(base);
+            assert!(!result.is_null());
+            CGColorSpace::from_ptr(result)
+        }
+    }
+
+    /// Returns the base color space of a pattern or indexed color space.
+    pub fn base_color_space(&self) -> Option<CGColorSpace> {
+        unsafe {
+            let base = CGColorSpaceGetBaseColorSpace(self.as_ptr());
+            if !base.is_null() {
+                Some(CGColorSpace::from_ptr(CFRetain(base as *const _) as *mut _))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the model of the color space, or `Unknown` for a model this crate doesn't
+    /// know about.
+    pub fn model(&self) -> CGColorSpaceModel {
+        match unsafe { CGColorSpaceGetModel(self.as_ptr()) } {
+            0 => CGColorSpaceModel::Monochrome,
+            1 => CGColorSpaceModel::RGB,
+            2 => CGColorSpaceModel::CMYK,
+            3 => CGColorSpaceModel::Lab,
+            4 => CGColorSpaceModel::DeviceN,
+            5 => CGColorSpaceModel::Indexed,
+            6 => CGColorSpaceModel::Pattern,
+            7 => CGColorSpaceModel::XYZ,
+            _ => CGColorSpaceModel::Unknown,
+        }
+    }
+
+    /// Returns the number of color components of the color space, not including alpha.
+    pub fn number_of_components(&self) -> usize {
+        unsafe {
+            CGColorSpaceGetNumberOfComponents(self.as_ptr())
+        }
+    }
+
+    pub fn create_device_gray() -> CGColorSpace {
+        unsafe {
+            let result = CGColorSpaceCreateDeviceGray();
+            CGColorSpace::from_ptr(result)
+        }
+    }
+
+    /// Returns the color space of `display`, falling back to sRGB (or, failing that, device RGB)
+    /// if the display doesn't report one. Unlike `CGDisplay::color_space`, this always returns a
+    /// usable color space.
+    #[cfg(target_os = "macos")]
+    pub fn for_display(display: &CGDisplay) -> CGColorSpace {
+        display.color_space()
+            .or_else(|| unsafe { CGColorSpace::create_with_name(kCGColorSpaceSRGB) })
+            .unwrap_or_else(CGColorSpace::create_device_rgb)
+    }
+}
+
+impl PartialEq for CGColorSpaceRef {
+    fn eq(&self, other: &CGColorSpaceRef) -> bool {
+        unsafe {
+            CFEqual(self.as_ptr() as *const _, other.as_ptr() as *const _) != 0
+        }
+    }
+}
+
+impl PartialEq for CGColorSpace {
+    fn eq(&self, other: &CGColorSpace) -> bool {
+        **self == **other
+    }
+}
+
+#[test]
+fn color_space_eq_test() {
+    assert!(CGColorSpace::create_srgb() == CGColorSpace::create_srgb());
+    assert!(CGColorSpace::create_srgb() != CGColorSpace::create_linear_gray().unwrap());
+}
+
+#[test]
+fn create_linear_gray_test() {
+    let gray = CGColorSpace::create_linear_gray().unwrap();
+    assert_eq!(gray.model(), CGColorSpaceModel::Monochrome);
+    assert_eq!(gray.number_of_components(), 1);
+
+    let extended = CGColorSpace::create_extended_gray().unwrap();
+    assert_eq!(extended.model(), CGColorSpaceModel::Monochrome);
+}
+
+#[test]
+fn base_color_space_test() {
+    let gray = CGColorSpace::create_device_gray();
+    let pattern = CGColorSpace::create_pattern(Some(&gray));
+    assert!(pattern.base_color_space().unwrap() == gray);
+    assert!(CGColorSpace::create_pattern(None).base_color_space().is_none());
+    assert!(gray.base_color_space().is_none());
+}
+
+#[cfg(target_os = "macos")]
+#[test]
+fn for_display_test() {
+    let color_space = CGColorSpace::for_display(&CGDisplay::main());
+    assert!(!color_space.as_ptr().is_null());
 }
 
 #[link(name = "CoreGraphics", kind = "framework")]
@@ -51,9 +206,16 @@ extern {
     pub static kCGColorSpaceGenericCMYK: CFStringRef;
     pub static kCGColorSpaceGenericRGBLinear: CFStringRef;
     pub static kCGColorSpaceGenericGrayGamma2_2: CFStringRef;
+    pub static kCGColorSpaceLinearGray: CFStringRef;
+    pub static kCGColorSpaceExtendedGray: CFStringRef;
 
     fn CGColorSpaceCreateDeviceRGB() -> ::sys::CGColorSpaceRef;
+    fn CGColorSpaceCreateDeviceGray() -> ::sys::CGColorSpaceRef;
     fn CGColorSpaceCreateWithName(name: CFStringRef) -> ::sys::CGColorSpaceRef;
+    fn CGColorSpaceCreatePattern(baseSpace: ::sys::CGColorSpaceRef) -> ::sys::CGColorSpaceRef;
+    fn CGColorSpaceGetBaseColorSpace(space: ::sys::CGColorSpaceRef) -> ::sys::CGColorSpaceRef;
     fn CGColorSpaceGetTypeID() -> CFTypeID;
+    fn CGColorSpaceGetModel(space: ::sys::CGColorSpaceRef) -> i32;
+    fn CGColorSpaceGetNumberOfComponents(space: ::sys::CGColorSpaceRef) -> size_t;
 }
 
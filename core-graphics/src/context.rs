@@ -7,21 +7,33 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use base::CGFloat;
-use color_space::CGColorSpace;
+use base::{CGFloat, kCGBitmapByteOrder32Host};
+use color::CGColor;
+use color_space::{CGColorSpace, CGColorSpaceModel, kCGColorSpaceSRGB};
 use core_foundation::base::{CFRelease, CFRetain, CFTypeID};
+use core_foundation::dictionary::CFDictionaryRef;
 use font::{CGFont, CGGlyph};
+use gradient::{CGGradient, CGGradientDrawingOptions};
 use geometry::CGPoint;
 use libc::{c_void, c_int, size_t};
 
 use std::cmp;
+use std::mem;
 use std::ptr;
 use std::slice;
-use geometry::{CGAffineTransform, CGRect};
+use geometry::{CGAffineTransform, CGRect, CGSize};
 use image::CGImage;
+use layer::CGLayer;
+use path::CGPath;
+use pattern::CGPattern;
+use shading::CGShading;
 use foreign_types::ForeignType;
+use base::{kCGBitmapAlphaInfoMask, kCGBitmapByteOrderMask, kCGBitmapByteOrder32Little};
+use base::kCGImageAlphaNone;
+use base::{kCGImageAlphaFirst, kCGImageAlphaNoneSkipFirst, kCGImageAlphaPremultipliedFirst};
 
 #[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CGTextDrawingMode {
     CGTextFill,
     CGTextStroke,
@@ -32,10 +44,53 @@ pub enum CGTextDrawingMode {
     CGTextClip
 }
 
+/// How `draw_path` paints the current path.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CGPathDrawingMode {
+    /// Fill using the nonzero winding rule.
+    Fill = 0,
+    /// Fill using the even-odd rule.
+    EOFill = 1,
+    Stroke = 2,
+    /// Fill using the nonzero winding rule, then stroke.
+    FillStroke = 3,
+    /// Fill using the even-odd rule, then stroke.
+    EOFillStroke = 4,
+}
+
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CGInterpolationQuality {
+    Default = 0,
+    None = 1,
+    Low = 2,
+    High = 3,
+    Medium = 4,
+}
+
+/// The kind of destination a `CGContext` draws into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CGContextType {
+    Unknown,
+    PDF,
+    PostScript,
+    Window,
+    Bitmap,
+    GL,
+    DisplayList,
+    KSeparation,
+    IOSurface,
+}
+
 foreign_type! {
     #[doc(hidden)]
     type CType = ::sys::CGContext;
-    fn drop = |cs| CFRelease(cs as *mut _);
+    fn drop = |cs| {
+        let last = gstate_depth::is_last_reference(cs);
+        CFRelease(cs as *mut _);
+        gstate_depth::release(cs, last);
+    };
     fn clone = |p| CFRetain(p as *const _) as *mut _;
     pub struct CGContext;
     pub struct CGContextRef;
@@ -69,11 +124,86 @@ impl CGContext {
         }
     }
 
+    /// Creates a bitmap context drawing into `data`, which the context owns
+    /// and frees once it is dropped. The pixels can be accessed through
+    /// `data`.
+    ///
+    /// Returns `None` if `bytes_per_row` is too small to hold `width` pixels,
+    /// if `data` is smaller than `height` rows of `bytes_per_row` bytes, or if
+    /// the format isn't supported. Unlike `create_bitmap_context`,
+    /// `bytes_per_row` can't be 0.
+    pub fn create_bitmap_context_with_data(data: Vec<u8>,
+                                           width: size_t,
+                                           height: size_t,
+                                           bits_per_component: size_t,
+                                           bytes_per_row: size_t,
+                                           space: &CGColorSpace,
+                                           bitmap_info: u32)
+                                           -> Option<CGContext> {
+        let alpha_components = if bitmap_info & kCGBitmapAlphaInfoMask == kCGImageAlphaNone {
+            0
+        } else {
+            1
+        };
+        let components = space.number_of_components() + alpha_components;
+        let min_bytes_per_row = match width.checked_mul(components)
+            .and_then(|n| n.checked_mul(bits_per_component)) {
+            Some(bits) => (bits + 7) / 8,
+            None => return None,
+        };
+        if bytes_per_row == 0 || bytes_per_row < min_bytes_per_row {
+            return None;
+        }
+        match height.checked_mul(bytes_per_row) {
+            Some(len) if data.len() >= len => {}
+            _ => return None,
+        }
+        let mut data = Box::new(data);
+        unsafe {
+            let result = CGBitmapContextCreateWithData(data.as_mut_ptr() as *mut c_void,
+                                                       width,
+                                                       height,
+                                                       bits_per_component,
+                                                       bytes_per_row,
+                                                       space.as_ptr(),
+                                                       bitmap_info,
+                                                       release_bitmap_data,
+                                                       &mut *data as *mut Vec<u8> as *mut _);
+            if result.is_null() {
+                return None;
+            }
+            // Freed by `release_bitmap_data`.
+            mem::forget(data);
+            Some(Self::from_ptr(result))
+        }
+    }
+
+    /// Creates a `width` x `height` bitmap context in the sRGB color space with 8-bit
+    /// premultiplied ARGB pixels in host byte order, the usual choice for drawing on screen.
+    ///
+    /// Falls back to the device RGB color space if sRGB isn't available.
+    pub fn create_argb_bitmap(width: size_t, height: size_t) -> CGContext {
+        let space = unsafe { CGColorSpace::create_with_name(kCGColorSpaceSRGB) }
+            .unwrap_or_else(CGColorSpace::create_device_rgb);
+        CGContext::create_bitmap_context(None,
+                                         width,
+                                         height,
+                                         8,
+                                         0,
+                                         &space,
+                                         kCGImageAlphaPremultipliedFirst |
+                                         kCGBitmapByteOrder32Host)
+    }
+
+    /// Returns the pixels of a bitmap context, `height` rows of
+    /// `bytes_per_row` bytes. Returns an empty slice for other contexts.
     pub fn data(&mut self) -> &mut [u8] {
         unsafe {
-            slice::from_raw_parts_mut(
-                    CGBitmapContextGetData(self.as_ptr()) as *mut u8,
-                    (self.height() * self.bytes_per_row()) as usize)
+            let data = CGBitmapContextGetData(self.as_ptr()) as *mut u8;
+            if data.is_null() {
+                return &mut [];
+            }
+            slice::from_raw_parts_mut(data, (self.height() * self.bytes_per_row()) as usize)
         }
     }
 
@@ -95,12 +225,280 @@ impl CGContext {
         }
     }
 
+    /// Returns the kind of destination the context draws into, e.g. to check that it is a
+    /// bitmap context before using the bitmap accessors.
+    ///
+    /// This relies on the private `CGContextGetType` function, which has been stable for many
+    /// releases. Types added in newer systems are reported as `Unknown`.
+    pub fn context_type(&self) -> CGContextType {
+        match unsafe { CGContextGetType(self.as_ptr()) } {
+            1 => CGContextType::PDF,
+            2 => CGContextType::PostScript,
+            3 => CGContextType::Window,
+            4 => CGContextType::Bitmap,
+            5 => CGContextType::GL,
+            6 => CGContextType::DisplayList,
+            7 => CGContextType::KSeparation,
+            8 => CGContextType::IOSurface,
+            _ => CGContextType::Unknown,
+        }
+    }
+
+    pub fn bits_per_component(&self) -> size_t {
+        unsafe {
+            CGBitmapContextGetBitsPerComponent(self.as_ptr())
+        }
+    }
+
+    pub fn bits_per_pixel(&self) -> size_t {
+        unsafe {
+            CGBitmapContextGetBitsPerPixel(self.as_ptr())
+        }
+    }
+
+    pub fn bitmap_info(&self) -> u32 {
+        unsafe {
+            CGBitmapContextGetBitmapInfo(self.as_ptr())
+        }
+    }
+
+    /// Writes a single pixel of a 32-bit RGB bitmap context directly into its backing buffer.
+    ///
+    /// `x` and `y` are in buffer coordinates, i.e. `(0, 0)` is the first pixel of the first row
+    /// in memory (the top-left corner of the image). The components are given in RGBA order and
+    /// are reordered to match the context's alpha position and byte order. No premultiplication
+    /// is performed.
+    ///
+    /// Fails if the pixel is out of bounds or the context doesn't use 8-bit components in 32-bit
+    /// pixels.
+    pub fn set_pixel(&mut self, x: usize, y: usize, rgba: [u8; 4]) -> Result<(), ()> {
+        let (offset, order) = match self.pixel_layout(x, y) {
+            Some(layout) => layout,
+            None => return Err(()),
+        };
+        let data = self.data();
+        for (channel, &index) in order.iter().enumerate() {
+            data[offset + index] = rgba[channel];
+        }
+        Ok(())
+    }
+
+    /// Reads a single pixel of a 32-bit RGB bitmap context directly from its backing buffer.
+    ///
+    /// See `set_pixel` for the coordinate system and component order. Returns `None` if the
+    /// pixel is out of bounds or the context doesn't use 8-bit components in 32-bit pixels.
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+        let (offset, order) = match self.pixel_layout(x, y) {
+            Some(layout) => layout,
+            None => return None,
+        };
+        let mut rgba = [0; 4];
+        unsafe {
+            let data = CGBitmapContextGetData(self.as_ptr()) as *const u8;
+            for (channel, &index) in order.iter().enumerate() {
+                rgba[channel] = *data.offset((offset + index) as isize);
+            }
+        }
+        Some(rgba)
+    }
+
+    // Returns the offset of the pixel at (`x`, `y`) in the buffer and the byte index within it of
+    // each of the R, G, B and A components, if the pixel is in bounds and its format is supported.
+    fn pixel_layout(&self, x: usize, y: usize) -> Option<(usize, [usize; 4])> {
+        if x >= self.width() || y >= self.height() ||
+           self.bits_per_pixel() != 32 || self.bits_per_component() != 8 {
+            return None;
+        }
+        let info = self.bitmap_info();
+        let alpha_info = info & kCGBitmapAlphaInfoMask;
+        let mut order = if alpha_info == kCGImageAlphaPremultipliedFirst ||
+                           alpha_info == kCGImageAlphaFirst ||
+                           alpha_info == kCGImageAlphaNoneSkipFirst {
+            [1, 2, 3, 0]
+        } else {
+            [0, 1, 2, 3]
+        };
+        if info & kCGBitmapByteOrderMask == kCGBitmapByteOrder32Little {
+            for index in order.iter_mut() {
+                *index = 3 - *index;
+            }
+        }
+        Some((y * self.bytes_per_row() + x * 4, order))
+    }
+
+    /// Forces all pending drawing to be rendered into the context's destination.
+    pub fn flush(&self) {
+        unsafe {
+            CGContextFlush(self.as_ptr())
+        }
+    }
+
+    /// Pushes a copy of the current graphics state onto the graphics state stack. Prefer
+    /// `saved_gstate` or `saved`, which can't leave the stack unbalanced.
+    ///
+    /// In debug builds, saves and restores made through this wrapper are counted per context.
+    /// `restore_gstate` without a matching `save_gstate`, or dropping the last reference to a
+    /// context with saves that were never restored, panics.
+    pub fn save_gstate(&self) {
+        gstate_depth::save(self.as_ptr());
+        unsafe {
+            CGContextSaveGState(self.as_ptr())
+        }
+    }
+
+    pub fn restore_gstate(&self) {
+        gstate_depth::restore(self.as_ptr());
+        unsafe {
+            CGContextRestoreGState(self.as_ptr())
+        }
+    }
+
+    /// Saves the graphics state, restoring it when the returned guard is dropped.
+    pub fn saved_gstate<'a>(&'a self) -> GStateGuard<'a> {
+        self.save_gstate();
+        GStateGuard(self)
+    }
+
+    /// Runs `f` with the graphics state saved beforehand and restored afterwards, even if `f`
+    /// panics, so that changes `f` makes to it don't leak out.
+    pub fn saved<R, F>(&self, f: F) -> R where F: FnOnce(&CGContext) -> R {
+        let _guard = self.saved_gstate();
+        f(self)
+    }
+
+    pub fn get_ctm(&self) -> CGAffineTransform {
+        unsafe {
+            CGContextGetCTM(self.as_ptr())
+        }
+    }
+
+    pub fn concat_ctm(&self, transform: CGAffineTransform) {
+        unsafe {
+            CGContextConcatCTM(self.as_ptr(), transform)
+        }
+    }
+
+    /// Concatenates a transform that scales by (`sx`, `sy`), then rotates by `rotation` radians
+    /// (counterclockwise in the default, y-up coordinate space), then translates by (`tx`, `ty`).
+    ///
+    /// In other words, content drawn at the origin afterwards is scaled and rotated about the
+    /// origin and then placed at (`tx`, `ty`) in the current user space. This is equivalent to
+    /// calling `translate`, `rotate` and `scale`, in that order.
+    pub fn set_transform(&self, tx: CGFloat, ty: CGFloat, sx: CGFloat, sy: CGFloat,
+                         rotation: CGFloat) {
+        let (sin, cos) = (rotation.sin(), rotation.cos());
+        self.concat_ctm(CGAffineTransform {
+            a: sx * cos,
+            b: sx * sin,
+            c: -sy * sin,
+            d: sy * cos,
+            tx: tx,
+            ty: ty,
+        })
+    }
+
+    pub fn clip_bounding_box(&self) -> CGRect {
+        unsafe {
+            CGContextGetClipBoundingBox(self.as_ptr())
+        }
+    }
+
+    pub fn set_line_width(&self, width: CGFloat) {
+        unsafe {
+            CGContextSetLineWidth(self.as_ptr(), width)
+        }
+    }
+
+    pub fn set_stroke_color(&self, color: &CGColor) {
+        unsafe {
+            CGContextSetStrokeColorWithColor(self.as_ptr(), color.as_ptr())
+        }
+    }
+
+    /// Strokes a sequence of line segments, each given by a pair of consecutive points.
+    pub fn stroke_line_segments(&self, points: &[CGPoint]) {
+        unsafe {
+            CGContextStrokeLineSegments(self.as_ptr(), points.as_ptr(), points.len())
+        }
+    }
+
+    /// Strokes a grid of 1-device-pixel wide lines every `spacing` device pixels across the
+    /// context's clip bounds, starting at the device origin.
+    ///
+    /// Antialiasing is disabled and the lines are aligned to pixel centers so they come out
+    /// crisp. The graphics state is left untouched.
+    pub fn draw_pixel_grid(&self, spacing: CGFloat, color: &CGColor) {
+        assert!(spacing > 0.);
+        let _guard = self.saved_gstate();
+        // Draw in device space.
+        self.concat_ctm(self.get_ctm().invert());
+        self.set_should_antialias(false);
+        self.set_line_width(1.);
+        self.set_stroke_color(color);
+
+        let bounds = self.clip_bounding_box();
+        let (min_x, min_y) = (bounds.origin.x, bounds.origin.y);
+        let (max_x, max_y) = (min_x + bounds.size.width, min_y + bounds.size.height);
+        let mut segments = vec![];
+        let mut x = (min_x / spacing).ceil() * spacing;
+        while x < max_x {
+            segments.push(CGPoint::new(x + 0.5, min_y));
+            segments.push(CGPoint::new(x + 0.5, max_y));
+            x += spacing;
+        }
+        let mut y = (min_y / spacing).ceil() * spacing;
+        while y < max_y {
+            segments.push(CGPoint::new(min_x, y + 0.5));
+            segments.push(CGPoint::new(max_x, y + 0.5));
+            y += spacing;
+        }
+        self.stroke_line_segments(&segments);
+    }
+
     pub fn set_rgb_fill_color(&self, red: CGFloat, green: CGFloat, blue: CGFloat, alpha: CGFloat) {
         unsafe {
             CGContextSetRGBFillColor(self.as_ptr(), red, green, blue, alpha)
         }
     }
 
+    pub fn set_fill_color_space(&self, space: &CGColorSpace) {
+        unsafe {
+            CGContextSetFillColorSpace(self.as_ptr(), space.as_ptr())
+        }
+    }
+
+    pub fn set_stroke_color_space(&self, space: &CGColorSpace) {
+        unsafe {
+            CGContextSetStrokeColorSpace(self.as_ptr(), space.as_ptr())
+        }
+    }
+
+    /// Fills with `pattern` from now on, setting the fill color space to `space`, a pattern
+    /// color space (see `CGColorSpace::create_pattern`). `components` is the color an
+    /// uncolored pattern is painted with, in the base color space of `space`, followed by
+    /// alpha, or just the alpha of a colored pattern.
+    ///
+    /// Panics if `space` isn't a pattern color space or `components` doesn't have one value
+    /// per component of its base color space plus alpha.
+    pub fn set_fill_pattern(&self, space: &CGColorSpace, pattern: &CGPattern,
+                            components: &[CGFloat]) {
+        check_pattern_components(space, components);
+        unsafe {
+            CGContextSetFillColorSpace(self.as_ptr(), space.as_ptr());
+            CGContextSetFillPattern(self.as_ptr(), pattern.as_ptr(), components.as_ptr())
+        }
+    }
+
+    /// Strokes with `pattern` from now on, like `set_fill_pattern`.
+    pub fn set_stroke_pattern(&self, space: &CGColorSpace, pattern: &CGPattern,
+                              components: &[CGFloat]) {
+        check_pattern_components(space, components);
+        unsafe {
+            CGContextSetStrokeColorSpace(self.as_ptr(), space.as_ptr());
+            CGContextSetStrokePattern(self.as_ptr(), pattern.as_ptr(), components.as_ptr())
+        }
+    }
+
     pub fn set_allows_font_smoothing(&self, allows_font_smoothing: bool) {
         unsafe {
             CGContextSetAllowsFontSmoothing(self.as_ptr(), allows_font_smoothing)
@@ -125,12 +523,37 @@ impl CGContext {
         }
     }
 
+    pub fn set_interpolation_quality(&self, quality: CGInterpolationQuality) {
+        unsafe {
+            CGContextSetInterpolationQuality(self.as_ptr(), quality)
+        }
+    }
+
+    pub fn interpolation_quality(&self) -> CGInterpolationQuality {
+        match unsafe { CGContextGetInterpolationQuality(self.as_ptr()) } {
+            1 => CGInterpolationQuality::None,
+            2 => CGInterpolationQuality::Low,
+            3 => CGInterpolationQuality::High,
+            4 => CGInterpolationQuality::Medium,
+            _ => CGInterpolationQuality::Default,
+        }
+    }
+
     pub fn set_should_antialias(&self, should_antialias: bool) {
         unsafe {
             CGContextSetShouldAntialias(self.as_ptr(), should_antialias)
         }
     }
 
+    /// Runs `f` with antialiasing turned `on` or off, restoring the previous setting (along with
+    /// the rest of the graphics state) afterwards.
+    pub fn with_antialiasing<R, F>(&self, on: bool, f: F) -> R where F: FnOnce(&CGContext) -> R {
+        self.saved(|ctx| {
+            ctx.set_should_antialias(on);
+            f(ctx)
+        })
+    }
+
     pub fn set_allows_font_subpixel_quantization(&self, allows_font_subpixel_quantization: bool) {
         unsafe {
             CGContextSetAllowsFontSubpixelQuantization(self.as_ptr(), allows_font_subpixel_quantization)
@@ -161,18 +584,363 @@ impl CGContext {
         }
     }
 
+    pub fn translate(&self, tx: CGFloat, ty: CGFloat) {
+        unsafe {
+            CGContextTranslateCTM(self.as_ptr(), tx, ty)
+        }
+    }
+
+    pub fn rotate(&self, angle: CGFloat) {
+        unsafe {
+            CGContextRotateCTM(self.as_ptr(), angle)
+        }
+    }
+
+    pub fn scale(&self, sx: CGFloat, sy: CGFloat) {
+        unsafe {
+            CGContextScaleCTM(self.as_ptr(), sx, sy)
+        }
+    }
+
+    /// Scales the current transformation matrix so that one point (1/72 inch) of subsequent
+    /// drawing covers `dpi / 72` units of the context's output, e.g. device pixels for a bitmap
+    /// context.
+    pub fn scale_for_dpi(&self, dpi: CGFloat) {
+        let scale = dpi / 72.;
+        self.scale(scale, scale)
+    }
+
     pub fn fill_rect(&self, rect: CGRect) {
         unsafe {
             CGContextFillRect(self.as_ptr(), rect)
         }
     }
 
+    pub fn begin_path(&self) {
+        unsafe {
+            CGContextBeginPath(self.as_ptr())
+        }
+    }
+
+    pub fn move_to_point(&self, x: CGFloat, y: CGFloat) {
+        unsafe {
+            CGContextMoveToPoint(self.as_ptr(), x, y)
+        }
+    }
+
+    /// Returns the end point of the current path, or `CGPointZero` if there is no current path.
+    pub fn current_point(&self) -> CGPoint {
+        unsafe {
+            CGContextGetPathCurrentPoint(self.as_ptr())
+        }
+    }
+
+    pub fn add_line_to_point(&self, x: CGFloat, y: CGFloat) {
+        unsafe {
+            CGContextAddLineToPoint(self.as_ptr(), x, y)
+        }
+    }
+
+    /// Adds a cubic Bézier curve from the current point to `(x, y)`, with the control
+    /// points `(cp1x, cp1y)` and `(cp2x, cp2y)`.
+    pub fn add_curve_to_point(&self, cp1x: CGFloat, cp1y: CGFloat, cp2x: CGFloat, cp2y: CGFloat,
+                              x: CGFloat, y: CGFloat) {
+        unsafe {
+            CGContextAddCurveToPoint(self.as_ptr(), cp1x, cp1y, cp2x, cp2y, x, y)
+        }
+    }
+
+    /// Adds a quadratic Bézier curve from the current point to `(x, y)`, with the control
+    /// point `(cpx, cpy)`.
+    pub fn add_quad_curve_to_point(&self, cpx: CGFloat, cpy: CGFloat, x: CGFloat, y: CGFloat) {
+        unsafe {
+            CGContextAddQuadCurveToPoint(self.as_ptr(), cpx, cpy, x, y)
+        }
+    }
+
+    /// Adds an arc of the circle around `(x, y)` from `start_angle` to `end_angle`, in
+    /// radians, to the current path, connected to the current point by a line if there is
+    /// one.
+    pub fn add_arc(&self, x: CGFloat, y: CGFloat, radius: CGFloat, start_angle: CGFloat,
+                   end_angle: CGFloat, clockwise: bool) {
+        unsafe {
+            CGContextAddArc(self.as_ptr(), x, y, radius, start_angle, end_angle,
+                            clockwise as c_int)
+        }
+    }
+
+    pub fn add_arc_to_point(&self, x1: CGFloat, y1: CGFloat, x2: CGFloat, y2: CGFloat,
+                            radius: CGFloat) {
+        unsafe {
+            CGContextAddArcToPoint(self.as_ptr(), x1, y1, x2, y2, radius)
+        }
+    }
+
+    pub fn close_path(&self) {
+        unsafe {
+            CGContextClosePath(self.as_ptr())
+        }
+    }
+
+    pub fn fill_path(&self) {
+        unsafe {
+            CGContextFillPath(self.as_ptr())
+        }
+    }
+
+    pub fn stroke_path(&self) {
+        unsafe {
+            CGContextStrokePath(self.as_ptr())
+        }
+    }
+
+    /// Paints the current path according to `mode`, consuming it.
+    pub fn draw_path(&self, mode: CGPathDrawingMode) {
+        unsafe {
+            CGContextDrawPath(self.as_ptr(), mode)
+        }
+    }
+
+    /// Adds the subpaths of `path`, in user space, to the current path.
+    pub fn add_path(&self, path: &CGPath) {
+        unsafe {
+            CGContextAddPath(self.as_ptr(), path.as_ptr())
+        }
+    }
+
+    pub fn add_rect(&self, rect: CGRect) {
+        unsafe {
+            CGContextAddRect(self.as_ptr(), rect)
+        }
+    }
+
+    /// Adds a closed subpath for `rect` with its corners rounded to quarter circles of
+    /// `corner_radius` to the current path, to be filled, stroked or clipped to afterwards.
+    ///
+    /// The radius is clamped to half the shorter side of `rect`.
+    pub fn add_rounded_rect(&self, rect: CGRect, corner_radius: CGFloat) {
+        let radius = corner_radius.max(0.).min(rect.size.width.min(rect.size.height) / 2.);
+        let (min_x, min_y) = (rect.origin.x, rect.origin.y);
+        let (max_x, max_y) = (min_x + rect.size.width, min_y + rect.size.height);
+        self.move_to_point(min_x + radius, min_y);
+        self.add_arc_to_point(max_x, min_y, max_x, max_y, radius);
+        self.add_arc_to_point(max_x, max_y, min_x, max_y, radius);
+        self.add_arc_to_point(min_x, max_y, min_x, min_y, radius);
+        self.add_arc_to_point(min_x, min_y, max_x, min_y, radius);
+        self.close_path();
+    }
+
+    pub fn add_ellipse_in_rect(&self, rect: CGRect) {
+        unsafe {
+            CGContextAddEllipseInRect(self.as_ptr(), rect)
+        }
+    }
+
+    pub fn clip(&self) {
+        unsafe {
+            CGContextClip(self.as_ptr())
+        }
+    }
+
+    /// Intersects the current clipping area with the current path using the even-odd rule,
+    /// consuming the path.
+    pub fn eo_clip(&self) {
+        unsafe {
+            CGContextEOClip(self.as_ptr())
+        }
+    }
+
+    pub fn clip_to_rect(&self, rect: CGRect) {
+        unsafe {
+            CGContextClipToRect(self.as_ptr(), rect)
+        }
+    }
+
+    /// Intersects the current clipping area with the union of `rects`.
+    pub fn clip_to_rects(&self, rects: &[CGRect]) {
+        unsafe {
+            CGContextClipToRects(self.as_ptr(), rects.as_ptr(), rects.len())
+        }
+    }
+
+    /// Masks drawing with `mask` stretched over `rect`: a grayscale image, whose samples act
+    /// as alpha, or an image with an alpha channel. Nothing is drawn outside `rect`.
+    pub fn clip_to_mask(&self, rect: CGRect, mask: &CGImage) {
+        unsafe {
+            CGContextClipToMask(self.as_ptr(), rect, mask.as_ptr())
+        }
+    }
+
+    /// Intersects the current clipping area with the ellipse inscribed in `rect`.
+    ///
+    /// Any path under construction is consumed, like with `clip`.
+    pub fn clip_to_ellipse_in_rect(&self, rect: CGRect) {
+        self.add_ellipse_in_rect(rect);
+        self.clip();
+    }
+
+    pub fn fill_ellipse_in_rect(&self, rect: CGRect) {
+        unsafe {
+            CGContextFillEllipseInRect(self.as_ptr(), rect)
+        }
+    }
+
+    pub fn stroke_ellipse_in_rect(&self, rect: CGRect) {
+        unsafe {
+            CGContextStrokeEllipseInRect(self.as_ptr(), rect)
+        }
+    }
+
     pub fn draw_image(&self, rect: CGRect, image: &CGImage) {
         unsafe {
             CGContextDrawImage(self.as_ptr(), rect, image.as_ptr());
         }
     }
 
+    /// Fills the clipping area with `gradient` varying along the line from `start` to `end`.
+    pub fn draw_linear_gradient(&self, gradient: &CGGradient, start: CGPoint, end: CGPoint,
+                                options: CGGradientDrawingOptions) {
+        unsafe {
+            CGContextDrawLinearGradient(self.as_ptr(), gradient.as_ptr(), start, end, options)
+        }
+    }
+
+    /// Fills the clipping area with `gradient` varying from the circle around `start_center`
+    /// of `start_radius` to the one around `end_center` of `end_radius`.
+    pub fn draw_radial_gradient(&self, gradient: &CGGradient,
+                                start_center: CGPoint, start_radius: CGFloat,
+                                end_center: CGPoint, end_radius: CGFloat,
+                                options: CGGradientDrawingOptions) {
+        unsafe {
+            CGContextDrawRadialGradient(self.as_ptr(), gradient.as_ptr(),
+                                        start_center, start_radius,
+                                        end_center, end_radius,
+                                        options)
+        }
+    }
+
+    /// Fills the clipping area with `shading`.
+    pub fn draw_shading(&self, shading: &CGShading) {
+        unsafe {
+            CGContextDrawShading(self.as_ptr(), shading.as_ptr())
+        }
+    }
+
+    /// Draws `layer` scaled to fill `rect`.
+    pub fn draw_layer_in_rect(&self, rect: CGRect, layer: &CGLayer) {
+        unsafe {
+            CGContextDrawLayerInRect(self.as_ptr(), rect, layer.as_ptr());
+        }
+    }
+
+    /// Draws `layer` at its own size with its origin at `point`.
+    pub fn draw_layer_at_point(&self, point: CGPoint, layer: &CGLayer) {
+        unsafe {
+            CGContextDrawLayerAtPoint(self.as_ptr(), point, layer.as_ptr());
+        }
+    }
+
+    /// Fills the clipping area with copies of `image`, each the size of `rect` and aligned
+    /// to its origin.
+    pub fn draw_tiled_image(&self, rect: CGRect, image: &CGImage) {
+        unsafe {
+            CGContextDrawTiledImage(self.as_ptr(), rect, image.as_ptr());
+        }
+    }
+
+    /// Draws `image` into `rect` with its opacity multiplied by `alpha`, e.g. for a watermark,
+    /// leaving the context's alpha as it was.
+    pub fn draw_image_with_alpha(&self, rect: CGRect, image: &CGImage, alpha: CGFloat) {
+        let _guard = self.saved_gstate();
+        self.set_alpha(alpha);
+        self.draw_image(rect, image);
+    }
+
+    pub fn set_alpha(&self, alpha: CGFloat) {
+        unsafe {
+            CGContextSetAlpha(self.as_ptr(), alpha)
+        }
+    }
+
+    /// Starts a transparency layer: until the matching `end_transparency_layer`, drawing
+    /// goes to an offscreen buffer that is then composited as a whole, using the alpha,
+    /// shadow and blend mode that were current when the layer began.
+    pub fn begin_transparency_layer(&self) {
+        unsafe {
+            CGContextBeginTransparencyLayer(self.as_ptr(), ptr::null())
+        }
+    }
+
+    /// Like `begin_transparency_layer`, with the layer's buffer bounded to `rect`.
+    pub fn begin_transparency_layer_with_rect(&self, rect: CGRect) {
+        unsafe {
+            CGContextBeginTransparencyLayerWithRect(self.as_ptr(), rect, ptr::null())
+        }
+    }
+
+    pub fn end_transparency_layer(&self) {
+        unsafe {
+            CGContextEndTransparencyLayer(self.as_ptr())
+        }
+    }
+
+    /// Draws with `f` into a transparency layer, bounded to `rect` if given, which is
+    /// composited when `f` returns or panics. This makes e.g. overlapping shapes drawn with
+    /// an alpha set beforehand fade as a group.
+    pub fn with_transparency_layer<R, F>(&self, rect: Option<CGRect>, f: F) -> R
+                                         where F: FnOnce(&CGContext) -> R {
+        struct LayerGuard<'a>(&'a CGContext);
+
+        impl<'a> Drop for LayerGuard<'a> {
+            fn drop(&mut self) {
+                self.0.end_transparency_layer()
+            }
+        }
+
+        match rect {
+            Some(rect) => self.begin_transparency_layer_with_rect(rect),
+            None => self.begin_transparency_layer(),
+        }
+        let _guard = LayerGuard(self);
+        f(self)
+    }
+
+    /// Draws `image` into `dest` as a nine-slice (nine-patch) image.
+    ///
+    /// `insets` gives the size of the fixed border as (top, left, bottom, right), in image pixels.
+    /// The four corners are drawn at that size, the top and bottom edges stretch horizontally, the
+    /// left and right edges stretch vertically, and the center stretches to fill the rest of
+    /// `dest`.
+    pub fn draw_nine_slice(&self, image: &CGImage, dest: CGRect,
+                           insets: (CGFloat, CGFloat, CGFloat, CGFloat)) {
+        let (top, left, bottom, right) = insets;
+        let (width, height) = (image.width() as CGFloat, image.height() as CGFloat);
+        // Source slices run top to bottom, as `CGImage` cropping expects; destination slices
+        // run bottom to top in user space.
+        let columns = [(0., left, dest.origin.x, left),
+                       (left, width - left - right, dest.origin.x + left,
+                        dest.size.width - left - right),
+                       (width - right, right, dest.origin.x + dest.size.width - right, right)];
+        let rows = [(0., top, dest.origin.y + dest.size.height - top, top),
+                    (top, height - top - bottom, dest.origin.y + bottom,
+                     dest.size.height - top - bottom),
+                    (height - bottom, bottom, dest.origin.y, bottom)];
+        for &(src_y, src_height, dest_y, dest_height) in &rows {
+            for &(src_x, src_width, dest_x, dest_width) in &columns {
+                if src_width <= 0. || src_height <= 0. || dest_width <= 0. || dest_height <= 0. {
+                    continue;
+                }
+                let src = CGRect::new(&CGPoint::new(src_x, src_y),
+                                      &CGSize::new(src_width, src_height));
+                if let Some(slice) = image.cropped_to(src) {
+                    let rect = CGRect::new(&CGPoint::new(dest_x, dest_y),
+                                           &CGSize::new(dest_width, dest_height));
+                    self.draw_image(rect, &slice);
+                }
+            }
+        }
+    }
+
     pub fn create_image(&self) -> Option<CGImage> {
         let image = unsafe { CGBitmapContextCreateImage(self.as_ptr()) };
         if !image.is_null() {
@@ -182,6 +950,15 @@ impl CGContext {
         }
     }
 
+    /// Returns an image of what has been drawn into the context so far, or `None` if the
+    /// context's destination can't be read back (e.g. a PDF context).
+    pub fn snapshot(&self) -> Option<CGImage> {
+        match self.context_type() {
+            CGContextType::Bitmap => self.create_image(),
+            _ => None,
+        }
+    }
+
     pub fn set_font(&self, font: &CGFont) {
         unsafe {
             CGContextSetFont(self.as_ptr(), font.as_ptr())
@@ -194,12 +971,34 @@ impl CGContext {
         }
     }
 
+    /// Sets the transform from text space to user space, applied to glyphs on top of the
+    /// font size.
     pub fn set_text_matrix(&self, t: &CGAffineTransform) {
         unsafe {
             CGContextSetTextMatrix(self.as_ptr(), *t)
         }
     }
 
+    pub fn text_matrix(&self) -> CGAffineTransform {
+        unsafe {
+            CGContextGetTextMatrix(self.as_ptr())
+        }
+    }
+
+    pub fn set_text_position(&self, x: CGFloat, y: CGFloat) {
+        unsafe {
+            CGContextSetTextPosition(self.as_ptr(), x, y)
+        }
+    }
+
+    pub fn text_position(&self) -> CGPoint {
+        unsafe {
+            CGContextGetTextPosition(self.as_ptr())
+        }
+    }
+
+    /// Draws `glyphs` of the current font at `positions`, in text space. Extra glyphs or
+    /// positions are ignored.
     pub fn show_glyphs_at_positions(&self, glyphs: &[CGGlyph], positions: &[CGPoint]) {
         unsafe {
             let count = cmp::min(glyphs.len(), positions.len());
@@ -211,6 +1010,107 @@ impl CGContext {
     }
 }
 
+/// Restores the graphics state of a context when dropped, see `CGContext::saved_gstate`.
+pub struct GStateGuard<'a>(&'a CGContext);
+
+impl<'a> Drop for GStateGuard<'a> {
+    fn drop(&mut self) {
+        self.0.restore_gstate()
+    }
+}
+
+// CG reads one value per component of the base color space of a pattern color space, plus
+// alpha, when setting a pattern.
+fn check_pattern_components(space: &CGColorSpace, components: &[CGFloat]) {
+    assert_eq!(space.model(), CGColorSpaceModel::Pattern, "not a pattern color space");
+    let base_components = space.base_color_space().map_or(0, |base| base.number_of_components());
+    assert_eq!(components.len(), base_components + 1, "wrong number of pattern components");
+}
+
+type CGBitmapContextReleaseDataCallback = unsafe extern "C" fn(*mut c_void, *mut c_void);
+
+unsafe extern "C" fn release_bitmap_data(release_info: *mut c_void, _: *mut c_void) {
+    drop(Box::from_raw(release_info as *mut Vec<u8>));
+}
+
+/// Counts the `save_gstate` calls of each context that haven't been restored yet, in debug
+/// builds, to catch unbalanced `save_gstate`/`restore_gstate` calls.
+///
+/// Contexts aren't `Send`, so the counts are kept per thread. A context only gets a count once
+/// it is saved through the wrapper, so restoring a save made elsewhere (e.g. by AppKit) on a
+/// context that was never saved through it isn't reported.
+#[cfg(debug_assertions)]
+mod gstate_depth {
+    use core_foundation::base::CFGetRetainCount;
+    use std::cell::RefCell;
+    use std::thread;
+
+    thread_local! {
+        static DEPTHS: RefCell<Vec<(usize, usize)>> = RefCell::new(Vec::new());
+    }
+
+    pub fn save(context: ::sys::CGContextRef) {
+        DEPTHS.with(|depths| {
+            let mut depths = depths.borrow_mut();
+            match depths.iter_mut().find(|entry| entry.0 == context as usize) {
+                Some(entry) => entry.1 += 1,
+                None => depths.push((context as usize, 1)),
+            }
+        })
+    }
+
+    pub fn restore(context: ::sys::CGContextRef) {
+        let balanced = DEPTHS.with(|depths| {
+            match depths.borrow_mut().iter_mut().find(|entry| entry.0 == context as usize) {
+                Some(entry) if entry.1 == 0 => false,
+                Some(entry) => {
+                    entry.1 -= 1;
+                    true
+                }
+                None => true,
+            }
+        });
+        if !balanced {
+            panic!("CGContext::restore_gstate called without a matching save_gstate");
+        }
+    }
+
+    pub fn is_last_reference(context: ::sys::CGContextRef) -> bool {
+        unsafe { CFGetRetainCount(context as *const _) == 1 }
+    }
+
+    /// Called once a reference to `context` has been released; `last` is whether it was the
+    /// last one.
+    pub fn release(context: ::sys::CGContextRef, last: bool) {
+        let depth = DEPTHS.with(|depths| {
+            let mut depths = depths.borrow_mut();
+            match depths.iter().position(|entry| entry.0 == context as usize) {
+                // Forget contexts with no open saves, so that a later context at the same address
+                // doesn't inherit their count.
+                Some(index) if last || depths[index].1 == 0 => depths.swap_remove(index).1,
+                _ => 0,
+            }
+        });
+        if last && depth != 0 && !thread::panicking() {
+            panic!("CGContext dropped with {} unrestored save_gstate call(s)", depth);
+        }
+    }
+}
+
+#[cfg(not(debug_assertions))]
+mod gstate_depth {
+    #[inline]
+    pub fn save(_: ::sys::CGContextRef) {}
+    #[inline]
+    pub fn restore(_: ::sys::CGContextRef) {}
+    #[inline]
+    pub fn is_last_reference(_: ::sys::CGContextRef) -> bool {
+        false
+    }
+    #[inline]
+    pub fn release(_: ::sys::CGContextRef, _: bool) {}
+}
+
 #[test]
 fn create_bitmap_context_test() {
     use geometry::*;
@@ -235,8 +1135,569 @@ fn create_bitmap_context_test() {
     assert_eq!(255, data.bytes()[3]);
 }
 
+#[test]
+fn create_bitmap_context_with_data_test() {
+    use geometry::*;
+
+    let cs = CGColorSpace::create_device_rgb();
+    assert!(CGContext::create_bitmap_context_with_data(vec![0; 15 * 16], 4, 16, 8, 16, &cs,
+                                                       ::base::kCGImageAlphaPremultipliedLast)
+        .is_none());
+    assert!(CGContext::create_bitmap_context_with_data(vec![], 4, 16, 8, 0, &cs,
+                                                       ::base::kCGImageAlphaPremultipliedLast)
+        .is_none());
+    assert!(CGContext::create_bitmap_context_with_data(vec![0; 12 * 16], 4, 16, 8, 12, &cs,
+                                                       ::base::kCGImageAlphaPremultipliedLast)
+        .is_none());
+    assert!(CGContext::create_bitmap_context_with_data(vec![], usize::max_value(), 2, 8,
+                                                       usize::max_value(), &cs,
+                                                       ::base::kCGImageAlphaPremultipliedLast)
+        .is_none());
+    let mut ctx = CGContext::create_bitmap_context_with_data(vec![0; 16 * 16], 4, 16, 8, 16, &cs,
+                                                             ::base::kCGImageAlphaPremultipliedLast)
+        .unwrap();
+    ctx.set_rgb_fill_color(1., 0., 0., 1.);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(4., 16.)));
+    assert_eq!(ctx.data().len(), 16 * 16);
+    assert_eq!(&ctx.data()[..4], &[255, 0, 0, 255]);
+}
+
+#[test]
+fn create_argb_bitmap_test() {
+    use geometry::*;
+
+    let mut ctx = CGContext::create_argb_bitmap(32, 16);
+    assert_eq!(ctx.width(), 32);
+    assert_eq!(ctx.height(), 16);
+    assert_eq!(ctx.bits_per_pixel(), 32);
+    assert!(ctx.bytes_per_row() >= 32 * 4);
+    assert_eq!(ctx.data().len(), ctx.bytes_per_row() * 16);
+    assert!(ctx.data().iter().all(|&byte| byte == 0));
+
+    ctx.set_rgb_fill_color(1., 0., 0., 1.);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(32., 16.)));
+    // Premultiplied ARGB in host byte order.
+    assert_eq!(ctx.get_pixel(0, 0).unwrap(), [255, 0, 0, 255]);
+    let argb = u32::from_be_bytes([255, 255, 0, 0]);
+    assert_eq!(&ctx.data()[..4], &argb.to_ne_bytes());
+}
+
+#[test]
+fn set_pixel_test() {
+    let cs = CGColorSpace::create_device_rgb();
+    let mut ctx = CGContext::create_bitmap_context(None,
+                                    4, 4,
+                                    8, 0,
+                                    &cs,
+                                    ::base::kCGImageAlphaPremultipliedFirst |
+                                    ::base::kCGBitmapByteOrder32Little);
+    ctx.set_pixel(2, 1, [10, 20, 30, 255]).unwrap();
+    assert_eq!([10, 20, 30, 255], ctx.get_pixel(2, 1).unwrap());
+    assert_eq!([0, 0, 0, 0], ctx.get_pixel(1, 2).unwrap());
+
+    assert_eq!(Err(()), ctx.set_pixel(4, 0, [10, 20, 30, 255]));
+    assert_eq!(None, ctx.get_pixel(0, 4));
+}
+
+#[test]
+fn scale_for_dpi_test() {
+    use geometry::*;
+
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None,
+                                200, 200,
+                                8, 0,
+                                &cs,
+                                ::base::kCGImageAlphaPremultipliedLast);
+    ctx.scale_for_dpi(144.);
+    ctx.set_rgb_fill_color(1., 0., 0., 1.);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(72., 72.)));
+    // The square covers the bottom-left 144x144 pixels, i.e. rows 56 to 199 of the buffer.
+    assert_eq!([255, 0, 0, 255], ctx.get_pixel(0, 199).unwrap());
+    assert_eq!([255, 0, 0, 255], ctx.get_pixel(143, 56).unwrap());
+    assert_eq!([0, 0, 0, 0], ctx.get_pixel(144, 56).unwrap());
+    assert_eq!([0, 0, 0, 0], ctx.get_pixel(143, 55).unwrap());
+}
+
+#[test]
+fn clip_to_ellipse_in_rect_test() {
+    use geometry::*;
+
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None,
+                                16, 16,
+                                8, 0,
+                                &cs,
+                                ::base::kCGImageAlphaPremultipliedLast);
+    let rect = CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(16., 16.));
+    ctx.clip_to_ellipse_in_rect(rect);
+    ctx.set_rgb_fill_color(1., 0., 0., 1.);
+    ctx.fill_rect(rect);
+    assert_eq!([255, 0, 0, 255], ctx.get_pixel(8, 8).unwrap());
+    for &(x, y) in &[(0, 0), (15, 0), (0, 15), (15, 15)] {
+        assert_eq!([0, 0, 0, 0], ctx.get_pixel(x, y).unwrap());
+    }
+}
+
+#[test]
+fn clip_to_rects_test() {
+    let ctx = CGContext::create_argb_bitmap(8, 1);
+    ctx.clip_to_rects(&[CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(2., 1.)),
+                        CGRect::new(&CGPoint::new(6., 0.), &CGSize::new(2., 1.))]);
+    ctx.clip_to_rect(CGRect::new(&CGPoint::new(1., 0.), &CGSize::new(7., 1.)));
+    assert_eq!(ctx.clip_bounding_box().size.width, 7.);
+    ctx.set_rgb_fill_color(1., 1., 1., 1.);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(8., 1.)));
+    assert_eq!(ctx.get_pixel(0, 0).unwrap(), [0, 0, 0, 0]);
+    assert_eq!(ctx.get_pixel(1, 0).unwrap(), [255, 255, 255, 255]);
+    assert_eq!(ctx.get_pixel(4, 0).unwrap(), [0, 0, 0, 0]);
+    assert_eq!(ctx.get_pixel(7, 0).unwrap(), [255, 255, 255, 255]);
+}
+
+#[test]
+fn eo_clip_test() {
+    let ctx = CGContext::create_argb_bitmap(4, 4);
+    ctx.add_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(4., 4.)));
+    ctx.add_rect(CGRect::new(&CGPoint::new(1., 1.), &CGSize::new(2., 2.)));
+    ctx.eo_clip();
+    ctx.set_rgb_fill_color(1., 1., 1., 1.);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(4., 4.)));
+    assert_eq!(ctx.get_pixel(0, 0).unwrap(), [255, 255, 255, 255]);
+    assert_eq!(ctx.get_pixel(2, 2).unwrap(), [0, 0, 0, 0]);
+}
+
+#[test]
+fn fill_ellipse_in_rect_test() {
+    use geometry::*;
+
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None,
+                                16, 16,
+                                8, 0,
+                                &cs,
+                                ::base::kCGImageAlphaPremultipliedLast);
+    ctx.set_rgb_fill_color(0., 0., 1., 1.);
+    ctx.fill_ellipse_in_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(16., 16.)));
+    assert_eq!([0, 0, 255, 255], ctx.get_pixel(8, 8).unwrap());
+    for &(x, y) in &[(0, 0), (15, 0), (0, 15), (15, 15)] {
+        assert_eq!([0, 0, 0, 0], ctx.get_pixel(x, y).unwrap());
+    }
+}
+
+#[test]
+fn with_antialiasing_test() {
+    use geometry::*;
+
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None,
+                                8, 8,
+                                8, 0,
+                                &cs,
+                                ::base::kCGImageAlphaPremultipliedLast);
+    ctx.set_should_antialias(false);
+    ctx.with_antialiasing(true, |_| {});
+    // With antialiasing still off, a rect that isn't pixel-aligned leaves no partially
+    // covered pixels.
+    ctx.set_rgb_fill_color(1., 1., 1., 1.);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0.5, 0.5), &CGSize::new(4., 4.)));
+    for y in 0..8 {
+        for x in 0..8 {
+            let alpha = ctx.get_pixel(x, y).unwrap()[3];
+            assert!(alpha == 0 || alpha == 255);
+        }
+    }
+}
+
+#[test]
+fn set_transform_test() {
+    use geometry::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None,
+                                10, 10,
+                                8, 0,
+                                &cs,
+                                ::base::kCGImageAlphaPremultipliedLast);
+    ctx.set_rgb_fill_color(1., 0., 0., 1.);
+    // A unit square at the origin, doubled and rotated a quarter turn, covers x in [3, 5] and
+    // y in [5, 7] once moved to (5, 5).
+    ctx.set_transform(5., 5., 2., 2., FRAC_PI_2 as CGFloat);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(1., 1.)));
+    for &(x, row) in &[(3, 3), (4, 3), (3, 4), (4, 4)] {
+        assert_eq!([255, 0, 0, 255], ctx.get_pixel(x, row).unwrap());
+    }
+    assert_eq!([0, 0, 0, 0], ctx.get_pixel(5, 3).unwrap());
+    assert_eq!([0, 0, 0, 0], ctx.get_pixel(3, 5).unwrap());
+}
+
+#[test]
+fn draw_nine_slice_test() {
+    use geometry::*;
+
+    let cs = CGColorSpace::create_device_rgb();
+    let source = CGContext::create_bitmap_context(None,
+                                6, 6,
+                                8, 0,
+                                &cs,
+                                ::base::kCGImageAlphaPremultipliedLast);
+    source.set_rgb_fill_color(0., 0., 1., 1.);
+    source.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(6., 6.)));
+    // Mark the top-left corner.
+    source.set_rgb_fill_color(1., 0., 0., 1.);
+    source.fill_rect(CGRect::new(&CGPoint::new(0., 4.), &CGSize::new(2., 2.)));
+    let image = source.create_image().unwrap();
+
+    let ctx = CGContext::create_bitmap_context(None,
+                                20, 20,
+                                8, 0,
+                                &cs,
+                                ::base::kCGImageAlphaPremultipliedLast);
+    ctx.draw_nine_slice(&image,
+                        CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(20., 20.)),
+                        (2., 2., 2., 2.));
+    // The corner keeps its 2x2 size...
+    for &(x, y) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+        assert_eq!([255, 0, 0, 255], ctx.get_pixel(x, y).unwrap());
+    }
+    for &(x, y) in &[(2, 0), (0, 2), (2, 2)] {
+        assert_eq!([0, 0, 255, 255], ctx.get_pixel(x, y).unwrap());
+    }
+    // ...while the center stretches to fill the rest.
+    assert_eq!([0, 0, 255, 255], ctx.get_pixel(10, 10).unwrap());
+    assert_eq!([0, 0, 255, 255], ctx.get_pixel(17, 17).unwrap());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "without a matching save_gstate")]
+fn unbalanced_restore_gstate_test() {
+    let ctx = CGContext::create_argb_bitmap(4, 4);
+    ctx.save_gstate();
+    ctx.restore_gstate();
+    ctx.restore_gstate();
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "unrestored save_gstate")]
+fn unbalanced_save_gstate_test() {
+    let ctx = CGContext::create_argb_bitmap(4, 4);
+    ctx.save_gstate();
+    drop(ctx);
+}
+
+#[test]
+fn saved_test() {
+    use std::panic::{self, AssertUnwindSafe};
+
+    let ctx = CGContext::create_argb_bitmap(4, 4);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        ctx.saved(|ctx| {
+            ctx.translate(1., 1.);
+            panic!("early exit");
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(ctx.get_ctm().tx, 0.);
+
+    {
+        let _guard = ctx.saved_gstate();
+        ctx.translate(1., 1.);
+    }
+    assert_eq!(ctx.get_ctm().tx, 0.);
+}
+
+#[test]
+fn draw_image_with_alpha_test() {
+    use geometry::*;
+
+    let cs = CGColorSpace::create_device_rgb();
+    let rect = CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(4., 4.));
+    let overlay = CGContext::create_bitmap_context(None, 4, 4, 8, 0, &cs,
+                                                   ::base::kCGImageAlphaPremultipliedLast);
+    overlay.set_rgb_fill_color(0., 0., 1., 1.);
+    overlay.fill_rect(rect);
+    let image = overlay.create_image().unwrap();
+
+    let ctx = CGContext::create_bitmap_context(None, 4, 4, 8, 0, &cs,
+                                               ::base::kCGImageAlphaPremultipliedLast);
+    ctx.set_rgb_fill_color(1., 0., 0., 1.);
+    ctx.fill_rect(rect);
+    ctx.draw_image_with_alpha(rect, &image, 0.5);
+    let [red, green, blue, alpha] = ctx.get_pixel(1, 1).unwrap();
+    assert!((red as i32 - 128).abs() <= 2);
+    assert_eq!(green, 0);
+    assert!((blue as i32 - 128).abs() <= 2);
+    assert_eq!(alpha, 255);
+}
+
+#[test]
+fn draw_tiled_image_test() {
+    use geometry::*;
+
+    let tile = CGContext::create_argb_bitmap(2, 2);
+    tile.set_rgb_fill_color(1., 1., 1., 1.);
+    tile.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(1., 1.)));
+    let image = tile.create_image().unwrap();
+
+    let ctx = CGContext::create_argb_bitmap(4, 4);
+    ctx.set_interpolation_quality(CGInterpolationQuality::None);
+    assert_eq!(ctx.interpolation_quality(), CGInterpolationQuality::None);
+    ctx.draw_tiled_image(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(2., 2.)), &image);
+    // Every tile has its bottom left pixel filled.
+    assert_eq!(ctx.get_pixel(0, 3).unwrap(), ctx.get_pixel(2, 1).unwrap());
+    assert_eq!(ctx.get_pixel(0, 3).unwrap(), [255, 255, 255, 255]);
+    assert_eq!(ctx.get_pixel(1, 3).unwrap(), [0, 0, 0, 0]);
+}
+
+#[test]
+fn draw_linear_gradient_test() {
+    use geometry::*;
+    use gradient::*;
+
+    let gradient = CGGradient::create_with_color_components(&CGColorSpace::create_device_rgb(),
+                                                            &[0., 0., 0., 1.,
+                                                              1., 1., 1., 1.],
+                                                            &[0., 1.]);
+    let ctx = CGContext::create_argb_bitmap(16, 1);
+    ctx.draw_linear_gradient(&gradient, CGPoint::new(4., 0.), CGPoint::new(12., 0.),
+                             kCGGradientDrawsBeforeStartLocation |
+                             kCGGradientDrawsAfterEndLocation);
+    assert_eq!(ctx.get_pixel(0, 0).unwrap(), [0, 0, 0, 255]);
+    assert_eq!(ctx.get_pixel(15, 0).unwrap(), [255, 255, 255, 255]);
+    let middle = ctx.get_pixel(8, 0).unwrap()[0];
+    assert!(middle > 64 && middle < 192);
+
+    // Without extending it, the gradient is only drawn between its ends.
+    let ctx = CGContext::create_argb_bitmap(16, 1);
+    ctx.draw_linear_gradient(&gradient, CGPoint::new(4., 0.), CGPoint::new(12., 0.), 0);
+    assert_eq!(ctx.get_pixel(0, 0).unwrap(), [0, 0, 0, 0]);
+}
+
+#[test]
+fn draw_shading_test() {
+    use geometry::*;
+
+    let shading = CGShading::new_radial(&CGColorSpace::create_device_gray(),
+                                        CGPoint::new(4., 4.), 0.,
+                                        CGPoint::new(4., 4.), 4.,
+                                        (false, true),
+                                        |t, gray| {
+                                            gray[0] = 1. - t;
+                                            gray[1] = 1.;
+                                        });
+    let ctx = CGContext::create_argb_bitmap(8, 8);
+    ctx.draw_shading(&shading);
+    assert!(ctx.get_pixel(4, 4).unwrap()[0] > 192);
+    assert_eq!(ctx.get_pixel(0, 0).unwrap(), [0, 0, 0, 255]);
+}
+
+#[test]
+fn set_fill_pattern_test() {
+    use geometry::*;
+    use pattern::CGPatternTiling;
+
+    // A checkerboard of 1x1 white squares.
+    let pattern = CGPattern::new(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(2., 2.)),
+                                 CGAffineTransform::new(1., 0., 0., 1., 0., 0.),
+                                 2., 2.,
+                                 CGPatternTiling::ConstantSpacing,
+                                 true,
+                                 |ctx| {
+                                     ctx.set_rgb_fill_color(1., 1., 1., 1.);
+                                     ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.),
+                                                               &CGSize::new(1., 1.)));
+                                     ctx.fill_rect(CGRect::new(&CGPoint::new(1., 1.),
+                                                               &CGSize::new(1., 1.)));
+                                 });
+    let ctx = CGContext::create_argb_bitmap(4, 4);
+    ctx.set_fill_pattern(&CGColorSpace::create_pattern(None), &pattern, &[1.]);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(4., 4.)));
+    let (first, second) = (ctx.get_pixel(0, 0).unwrap(), ctx.get_pixel(1, 0).unwrap());
+    assert_eq!(Some(first), ctx.get_pixel(2, 2));
+    assert_ne!(first, second);
+    assert_eq!(first[3] as u32 + second[3] as u32, 255);
+}
+
+#[test]
+#[should_panic(expected = "wrong number of pattern components")]
+fn set_fill_pattern_components_test() {
+    use geometry::*;
+    use pattern::CGPatternTiling;
+
+    let pattern = CGPattern::new(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(1., 1.)),
+                                 CGAffineTransform::new(1., 0., 0., 1., 0., 0.),
+                                 1., 1.,
+                                 CGPatternTiling::ConstantSpacing,
+                                 false,
+                                 |ctx| ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.),
+                                                                 &CGSize::new(1., 1.))));
+    let space = CGColorSpace::create_pattern(Some(&CGColorSpace::create_device_rgb()));
+    let ctx = CGContext::create_argb_bitmap(1, 1);
+    ctx.set_fill_pattern(&space, &pattern, &[1.]);
+}
+
+#[test]
+fn draw_layer_test() {
+    use geometry::*;
+
+    let ctx = CGContext::create_argb_bitmap(4, 4);
+    let layer = CGLayer::new(&ctx, CGSize::new(2., 2.)).unwrap();
+    assert_eq!(layer.size().width, 2.);
+    let layer_ctx = layer.context();
+    layer_ctx.set_rgb_fill_color(1., 1., 1., 1.);
+    layer_ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(2., 2.)));
+    ctx.draw_layer_at_point(CGPoint::new(2., 2.), &layer);
+    assert_eq!(ctx.get_pixel(3, 0).unwrap(), [255, 255, 255, 255]);
+    assert_eq!(ctx.get_pixel(1, 0).unwrap(), [0, 0, 0, 0]);
+    ctx.draw_layer_in_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(4., 4.)), &layer);
+    assert_eq!(ctx.get_pixel(1, 0).unwrap(), [255, 255, 255, 255]);
+}
+
+#[test]
+fn current_point_test() {
+    let ctx = CGContext::create_argb_bitmap(32, 32);
+    ctx.begin_path();
+    ctx.move_to_point(10., 20.);
+    let point = ctx.current_point();
+    assert_eq!(point.x, 10.);
+    assert_eq!(point.y, 20.);
+}
+
+#[test]
+fn draw_path_test() {
+    let ctx = CGContext::create_argb_bitmap(4, 4);
+    ctx.set_rgb_fill_color(1., 1., 1., 1.);
+    ctx.begin_path();
+    ctx.move_to_point(0., 0.);
+    ctx.add_line_to_point(4., 0.);
+    ctx.add_quad_curve_to_point(4., 2., 4., 4.);
+    ctx.add_curve_to_point(3., 4., 1., 4., 0., 4.);
+    ctx.close_path();
+    assert_eq!(ctx.current_point().x, 0.);
+    ctx.draw_path(CGPathDrawingMode::Fill);
+    assert_eq!(ctx.get_pixel(2, 2).unwrap(), [255, 255, 255, 255]);
+
+    // Even-odd filling leaves the inner square of two nested ones empty.
+    ctx.set_rgb_fill_color(0., 0., 0., 1.);
+    ctx.add_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(4., 4.)));
+    ctx.add_rect(CGRect::new(&CGPoint::new(1., 1.), &CGSize::new(2., 2.)));
+    ctx.draw_path(CGPathDrawingMode::EOFill);
+    assert_eq!(ctx.get_pixel(0, 0).unwrap(), [0, 0, 0, 255]);
+    assert_eq!(ctx.get_pixel(2, 2).unwrap(), [255, 255, 255, 255]);
+}
+
+#[test]
+fn text_matrix_test() {
+    let ctx = CGContext::create_argb_bitmap(4, 4);
+    ctx.set_text_matrix(&CGAffineTransform::new(2., 0., 0., 2., 0., 0.));
+    assert_eq!(ctx.text_matrix().a, 2.);
+    ctx.set_text_position(1., 3.);
+    let position = ctx.text_position();
+    assert_eq!((position.x, position.y), (1., 3.));
+}
+
+#[test]
+fn with_transparency_layer_test() {
+    let ctx = CGContext::create_argb_bitmap(4, 1);
+    ctx.set_alpha(0.5);
+    ctx.set_rgb_fill_color(1., 1., 1., 1.);
+    ctx.with_transparency_layer(None, |ctx| {
+        ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(3., 1.)));
+        ctx.fill_rect(CGRect::new(&CGPoint::new(1., 0.), &CGSize::new(3., 1.)));
+    });
+    // The overlap isn't any more opaque than the rest of the group.
+    assert_eq!(ctx.get_pixel(0, 0).unwrap(), ctx.get_pixel(1, 0).unwrap());
+    assert!(ctx.get_pixel(1, 0).unwrap()[3] < 255);
+}
+
+#[test]
+fn add_rounded_rect_test() {
+    use geometry::*;
+
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None,
+                                20, 20,
+                                8, 0,
+                                &cs,
+                                ::base::kCGImageAlphaPremultipliedLast);
+    ctx.set_rgb_fill_color(1., 0., 0., 1.);
+    ctx.begin_path();
+    ctx.add_rounded_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(20., 20.)), 8.);
+    ctx.fill_path();
+    for &(x, y) in &[(0, 0), (19, 0), (0, 19), (19, 19)] {
+        assert_eq!([0, 0, 0, 0], ctx.get_pixel(x, y).unwrap());
+    }
+    for &(x, y) in &[(10, 0), (0, 10), (19, 10), (10, 19), (10, 10)] {
+        assert_eq!([255, 0, 0, 255], ctx.get_pixel(x, y).unwrap());
+    }
+}
+
+#[test]
+fn draw_pixel_grid_test() {
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None,
+                                10, 10,
+                                8, 0,
+                                &cs,
+                                ::base::kCGImageAlphaPremultipliedLast);
+    ctx.draw_pixel_grid(4., &CGColor::rgb(1., 0., 0., 1.));
+    // Horizontal lines are at device rows 0, 4 and 8, counted from the bottom.
+    for &row in &[9, 5, 1] {
+        assert_eq!([255, 0, 0, 255], ctx.get_pixel(1, row).unwrap());
+    }
+    for &row in &[8, 7, 6, 4, 3, 2, 0] {
+        assert_eq!([0, 0, 0, 0], ctx.get_pixel(1, row).unwrap());
+    }
+    // Vertical lines are at device columns 0, 4 and 8.
+    assert_eq!([255, 0, 0, 255], ctx.get_pixel(4, 3).unwrap());
+    assert_eq!([0, 0, 0, 0], ctx.get_pixel(5, 3).unwrap());
+}
+
+#[test]
+fn context_type_test() {
+    use core_foundation::url::CFURL;
+    use geometry::*;
+    use pdf_context::CGPDFContext;
+
+    let bitmap = CGContext::create_argb_bitmap(4, 4);
+    assert_eq!(bitmap.context_type(), CGContextType::Bitmap);
+
+    let path = ::std::env::temp_dir().join("core-graphics-context-type-test.pdf");
+    let url = CFURL::from_path(&path, false).unwrap();
+    let media_box = CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(100., 100.));
+    let pdf = CGPDFContext::create_with_url(&url, Some(media_box)).unwrap();
+    assert_eq!(pdf.context_type(), CGContextType::PDF);
+    pdf.close();
+    drop(pdf);
+    let _ = ::std::fs::remove_file(path);
+}
+
+#[test]
+fn flush_test() {
+    use geometry::*;
+
+    let ctx = CGContext::create_argb_bitmap(2, 2);
+    ctx.set_rgb_fill_color(0., 1., 0., 1.);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(2., 2.)));
+    ctx.flush();
+    assert_eq!(ctx.get_pixel(1, 1).unwrap(), [0, 255, 0, 255]);
+}
+
+#[test]
+fn snapshot_test() {
+    let ctx = CGContext::create_argb_bitmap(12, 7);
+    let image = ctx.snapshot().unwrap();
+    assert_eq!(image.width(), 12);
+    assert_eq!(image.height(), 7);
+}
+
 #[link(name = "CoreGraphics", kind = "framework")]
 extern {
+    // Private, but used by e.g. WebKit to tell bitmap contexts apart.
+    fn CGContextGetType(c: ::sys::CGContextRef) -> c_int;
+
     fn CGBitmapContextCreate(data: *mut c_void,
                              width: size_t,
                              height: size_t,
@@ -245,16 +1706,38 @@ extern {
                              space: ::sys::CGColorSpaceRef,
                              bitmapInfo: u32)
                              -> ::sys::CGContextRef;
+    fn CGBitmapContextCreateWithData(data: *mut c_void,
+                                     width: size_t,
+                                     height: size_t,
+                                     bitsPerComponent: size_t,
+                                     bytesPerRow: size_t,
+                                     space: ::sys::CGColorSpaceRef,
+                                     bitmapInfo: u32,
+                                     releaseCallback: CGBitmapContextReleaseDataCallback,
+                                     releaseInfo: *mut c_void)
+                                     -> ::sys::CGContextRef;
     fn CGBitmapContextGetData(context: ::sys::CGContextRef) -> *mut c_void;
     fn CGBitmapContextGetWidth(context: ::sys::CGContextRef) -> size_t;
     fn CGBitmapContextGetHeight(context: ::sys::CGContextRef) -> size_t;
     fn CGBitmapContextGetBytesPerRow(context: ::sys::CGContextRef) -> size_t;
+    fn CGBitmapContextGetBitsPerComponent(context: ::sys::CGContextRef) -> size_t;
+    fn CGBitmapContextGetBitsPerPixel(context: ::sys::CGContextRef) -> size_t;
+    fn CGBitmapContextGetBitmapInfo(context: ::sys::CGContextRef) -> u32;
     fn CGBitmapContextCreateImage(context: ::sys::CGContextRef) -> ::sys::CGImageRef;
     fn CGContextGetTypeID() -> CFTypeID;
+    fn CGContextFlush(c: ::sys::CGContextRef);
     fn CGContextSetAllowsFontSmoothing(c: ::sys::CGContextRef, allowsFontSmoothing: bool);
     fn CGContextSetShouldSmoothFonts(c: ::sys::CGContextRef, shouldSmoothFonts: bool);
     fn CGContextSetFontSmoothingStyle(c: ::sys::CGContextRef, style: c_int);
     fn CGContextSetAllowsAntialiasing(c: ::sys::CGContextRef, allowsAntialiasing: bool);
+    fn CGContextSetAlpha(c: ::sys::CGContextRef, alpha: CGFloat);
+    fn CGContextBeginTransparencyLayer(c: ::sys::CGContextRef, auxiliaryInfo: CFDictionaryRef);
+    fn CGContextBeginTransparencyLayerWithRect(c: ::sys::CGContextRef,
+                                               rect: CGRect,
+                                               auxiliaryInfo: CFDictionaryRef);
+    fn CGContextEndTransparencyLayer(c: ::sys::CGContextRef);
+    fn CGContextSetInterpolationQuality(c: ::sys::CGContextRef, quality: CGInterpolationQuality);
+    fn CGContextGetInterpolationQuality(c: ::sys::CGContextRef) -> i32;
     fn CGContextSetShouldAntialias(c: ::sys::CGContextRef, shouldAntialias: bool);
     fn CGContextSetAllowsFontSubpixelQuantization(c: ::sys::CGContextRef,
                                                   allowsFontSubpixelQuantization: bool);
@@ -272,10 +1755,95 @@ extern {
                                 alpha: CGFloat);
     fn CGContextFillRect(context: ::sys::CGContextRef,
                          rect: CGRect);
+    fn CGContextSaveGState(c: ::sys::CGContextRef);
+    fn CGContextRestoreGState(c: ::sys::CGContextRef);
+    fn CGContextGetCTM(c: ::sys::CGContextRef) -> CGAffineTransform;
+    fn CGContextConcatCTM(c: ::sys::CGContextRef, transform: CGAffineTransform);
+    fn CGContextGetClipBoundingBox(c: ::sys::CGContextRef) -> CGRect;
+    fn CGContextSetLineWidth(c: ::sys::CGContextRef, width: CGFloat);
+    fn CGContextSetStrokeColorWithColor(c: ::sys::CGContextRef, color: ::sys::CGColorRef);
+    fn CGContextSetFillColorSpace(c: ::sys::CGContextRef, space: ::sys::CGColorSpaceRef);
+    fn CGContextSetStrokeColorSpace(c: ::sys::CGContextRef, space: ::sys::CGColorSpaceRef);
+    fn CGContextSetFillPattern(c: ::sys::CGContextRef,
+                               pattern: ::sys::CGPatternRef,
+                               components: *const CGFloat);
+    fn CGContextSetStrokePattern(c: ::sys::CGContextRef,
+                                 pattern: ::sys::CGPatternRef,
+                                 components: *const CGFloat);
+    fn CGContextStrokeLineSegments(c: ::sys::CGContextRef,
+                                   points: *const CGPoint,
+                                   count: size_t);
+    fn CGContextFillEllipseInRect(c: ::sys::CGContextRef, rect: CGRect);
+    fn CGContextStrokeEllipseInRect(c: ::sys::CGContextRef, rect: CGRect);
+    fn CGContextAddEllipseInRect(c: ::sys::CGContextRef, rect: CGRect);
+    fn CGContextBeginPath(c: ::sys::CGContextRef);
+    fn CGContextMoveToPoint(c: ::sys::CGContextRef, x: CGFloat, y: CGFloat);
+    fn CGContextGetPathCurrentPoint(c: ::sys::CGContextRef) -> CGPoint;
+    fn CGContextAddArcToPoint(c: ::sys::CGContextRef,
+                              x1: CGFloat,
+                              y1: CGFloat,
+                              x2: CGFloat,
+                              y2: CGFloat,
+                              radius: CGFloat);
+    fn CGContextAddLineToPoint(c: ::sys::CGContextRef, x: CGFloat, y: CGFloat);
+    fn CGContextAddCurveToPoint(c: ::sys::CGContextRef,
+                                cp1x: CGFloat,
+                                cp1y: CGFloat,
+                                cp2x: CGFloat,
+                                cp2y: CGFloat,
+                                x: CGFloat,
+                                y: CGFloat);
+    fn CGContextAddQuadCurveToPoint(c: ::sys::CGContextRef,
+                                    cpx: CGFloat,
+                                    cpy: CGFloat,
+                                    x: CGFloat,
+                                    y: CGFloat);
+    fn CGContextAddArc(c: ::sys::CGContextRef,
+                       x: CGFloat,
+                       y: CGFloat,
+                       radius: CGFloat,
+                       startAngle: CGFloat,
+                       endAngle: CGFloat,
+                       clockwise: c_int);
+    fn CGContextAddRect(c: ::sys::CGContextRef, rect: CGRect);
+    fn CGContextAddPath(c: ::sys::CGContextRef, path: ::sys::CGPathRef);
+    fn CGContextClosePath(c: ::sys::CGContextRef);
+    fn CGContextFillPath(c: ::sys::CGContextRef);
+    fn CGContextStrokePath(c: ::sys::CGContextRef);
+    fn CGContextDrawPath(c: ::sys::CGContextRef, mode: CGPathDrawingMode);
+    fn CGContextClip(c: ::sys::CGContextRef);
+    fn CGContextEOClip(c: ::sys::CGContextRef);
+    fn CGContextClipToRect(c: ::sys::CGContextRef, rect: CGRect);
+    fn CGContextClipToRects(c: ::sys::CGContextRef, rects: *const CGRect, count: size_t);
+    fn CGContextClipToMask(c: ::sys::CGContextRef, rect: CGRect, mask: ::sys::CGImageRef);
+    fn CGContextTranslateCTM(c: ::sys::CGContextRef, tx: CGFloat, ty: CGFloat);
+    fn CGContextRotateCTM(c: ::sys::CGContextRef, angle: CGFloat);
+    fn CGContextScaleCTM(c: ::sys::CGContextRef, sx: CGFloat, sy: CGFloat);
     fn CGContextDrawImage(c: ::sys::CGContextRef, rect: CGRect, image: ::sys::CGImageRef);
+    fn CGContextDrawTiledImage(c: ::sys::CGContextRef, rect: CGRect, image: ::sys::CGImageRef);
+    fn CGContextDrawLinearGradient(c: ::sys::CGContextRef,
+                                   gradient: ::sys::CGGradientRef,
+                                   startPoint: CGPoint,
+                                   endPoint: CGPoint,
+                                   options: CGGradientDrawingOptions);
+    fn CGContextDrawRadialGradient(c: ::sys::CGContextRef,
+                                   gradient: ::sys::CGGradientRef,
+                                   startCenter: CGPoint,
+                                   startRadius: CGFloat,
+                                   endCenter: CGPoint,
+                                   endRadius: CGFloat,
+                                   options: CGGradientDrawingOptions);
+    fn CGContextDrawShading(c: ::sys::CGContextRef, shading: ::sys::CGShadingRef);
+    fn CGContextDrawLayerInRect(c: ::sys::CGContextRef, rect: CGRect, layer: ::sys::CGLayerRef);
+    fn CGContextDrawLayerAtPoint(c: ::sys::CGContextRef,
+                                 point: CGPoint,
+                                 layer: ::sys::CGLayerRef);
     fn CGContextSetFont(c: ::sys::CGContextRef, font: ::sys::CGFontRef);
     fn CGContextSetFontSize(c: ::sys::CGContextRef, size: CGFloat);
     fn CGContextSetTextMatrix(c: ::sys::CGContextRef, t: CGAffineTransform);
+    fn CGContextGetTextMatrix(c: ::sys::CGContextRef) -> CGAffineTransform;
+    fn CGContextSetTextPosition(c: ::sys::CGContextRef, x: CGFloat, y: CGFloat);
+    fn CGContextGetTextPosition(c: ::sys::CGContextRef) -> CGPoint;
     fn CGContextShowGlyphsAtPositions(c: ::sys::CGContextRef,
                                       glyphs: *const CGGlyph,
                                       positions: *const CGPoint,
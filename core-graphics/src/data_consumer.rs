@@ -0,0 +1,85 @@
+// Copyright 2018 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use core_foundation::base::{CFRelease, CFRetain, CFTypeID};
+
+use libc::{c_void, size_t};
+use std::slice;
+use std::sync::{Arc, Mutex};
+
+use foreign_types::ForeignType;
+
+pub type CGDataConsumerPutBytesCallback = Option<unsafe extern fn (*mut c_void, *const c_void, size_t) -> size_t>;
+pub type CGDataConsumerReleaseInfoCallback = Option<unsafe extern fn (*mut c_void)>;
+
+#[repr(C)]
+pub struct CGDataConsumerCallbacks {
+    pub put_bytes: CGDataConsumerPutBytesCallback,
+    pub release_consumer: CGDataConsumerReleaseInfoCallback,
+}
+
+foreign_type! {
+    #[doc(hidden)]
+    type CType = ::sys::CGDataConsumer;
+    fn drop = |cs| CFRelease(cs as *mut _);
+    fn clone = |p| CFRetain(p as *const _) as *mut _;
+    pub struct CGDataConsumer;
+    pub struct CGDataConsumerRef;
+}
+
+impl CGDataConsumer {
+    pub fn type_id() -> CFTypeID {
+        unsafe {
+            CGDataConsumerGetTypeID()
+        }
+    }
+
+    /// Creates a data consumer that appends everything written to it to the given shared buffer.
+    ///
+    /// The `CGDataConsumer` object holds a reference to the buffer until it is destroyed.
+    pub fn from_buffer(buffer: Arc<Mutex<Vec<u8>>>) -> Self {
+        unsafe {
+            let info = Arc::into_raw(buffer) as *mut c_void;
+            let callbacks = CGDataConsumerCallbacks {
+                put_bytes: Some(put_bytes),
+                release_consumer: Some(release),
+            };
+            let result = CGDataConsumerCreate(info, &callbacks);
+            assert!(!result.is_null());
+            return CGDataConsumer::from_ptr(result);
+        }
+
+        unsafe extern "C" fn put_bytes(info: *mut c_void, data: *const c_void, count: size_t)
+                                       -> size_t {
+            let buffer = &*(info as *const Mutex<Vec<u8>>);
+            let bytes = slice::from_raw_parts(data as *const u8, count);
+            match buffer.lock() {
+                Ok(mut buffer) => {
+                    buffer.extend_from_slice(bytes);
+                    count
+                }
+                Err(_) => 0,
+            }
+        }
+
+        unsafe extern "C" fn release(info: *mut c_void) {
+            drop(Arc::from_raw(info as *const Mutex<Vec<u8>>))
+        }
+    }
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern {
+    fn CGDataConsumerCreate(info: *mut c_void,
+                            cbks: *const CGDataConsumerCallbacks)
+                            -> ::sys::CGDataConsumerRef;
+    //fn CGDataConsumerCreateWithCFData
+    //fn CGDataConsumerCreateWithURL
+    fn CGDataConsumerGetTypeID() -> CFTypeID;
+}
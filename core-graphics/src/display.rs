@@ -10,13 +10,28 @@
 #![allow(non_upper_case_globals)]
 
 use libc;
+use std::cmp;
+use std::fmt;
+use std::mem;
 use std::ptr;
-pub use base::{CGError, boolean_t};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+pub use base::{CGError, CGErrorCode, boolean_t};
 pub use geometry::{CGRect, CGPoint, CGSize};
 
-use core_foundation::base::{CFRetain, TCFType};
+use core_foundation::base::{CFRetain, CFType, TCFType};
+use core_foundation::number::CFNumber;
+use core_foundation::string::{CFString, CFStringRef};
+use base::{CGFloat, kCGBitmapByteOrder32Little};
+use base::kCGImageAlphaPremultipliedFirst;
+use color_space::CGColorSpace;
+use context::{CGContext, CGContextRef};
+use event::CGEvent;
+use event_source::{CGEventSource, CGEventSourceStateID};
 use image::CGImage;
-use foreign_types::ForeignType;
+use private::CGSCursor;
+use foreign_types::{ForeignType, ForeignTypeRef};
 
 pub type CGDirectDisplayID = libc::uint32_t;
 pub type CGWindowID        = libc::uint32_t;
@@ -24,34 +39,267 @@ pub type CGWindowID        = libc::uint32_t;
 pub const kCGNullWindowID: CGWindowID = 0 as CGWindowID;
 
 
-pub type CGWindowListOption = libc::uint32_t;
+pub enum CGDisplayConfig {}
+pub type CGDisplayConfigRef = *mut CGDisplayConfig;
 
-pub const kCGWindowListOptionAll:              CGWindowListOption    = 0;
-pub const kCGWindowListOptionOnScreenOnly:     CGWindowListOption    = 1 << 0;
-pub const kCGWindowListOptionOnScreenAboveWindow: CGWindowListOption = 1 << 1;
-pub const kCGWindowListOptionOnScreenBelowWindow: CGWindowListOption = 1 << 2;
-pub const kCGWindowListOptionIncludingWindow:  CGWindowListOption    = 1 << 3;
-pub const kCGWindowListExcludeDesktopElements: CGWindowListOption    = 1 << 4;
+pub type CGConfigureOption = libc::uint32_t;
 
-pub type CGWindowImageOption = libc::uint32_t;
+pub const kCGConfigureForAppOnly: CGConfigureOption = 0;
+pub const kCGConfigureForSession: CGConfigureOption = 1;
+pub const kCGConfigurePermanently: CGConfigureOption = 2;
 
-pub const kCGWindowImageDefault: CGWindowImageOption = 0;
-pub const kCGWindowImageBoundsIgnoreFraming: CGWindowImageOption = 1 << 0;
-pub const kCGWindowImageShouldBeOpaque: CGWindowImageOption = 1 << 1;
-pub const kCGWindowImageOnlyShadows: CGWindowImageOption = 1 << 2;
-pub const kCGWindowImageBestResolution: CGWindowImageOption = 1 << 3;
-pub const kCGWindowImageNominalResolution: CGWindowImageOption = 1 << 4;
+pub type CGDisplayFadeReservationToken = libc::uint32_t;
+pub type CGDisplayFadeInterval = libc::c_float;
+pub type CGDisplayReservationInterval = libc::c_float;
+pub type CGDisplayBlendFraction = libc::c_float;
+
+pub const kCGDisplayFadeReservationInvalidToken: CGDisplayFadeReservationToken = 0;
+pub const kCGDisplayBlendNormal: CGDisplayBlendFraction = 0.0;
+pub const kCGDisplayBlendSolidColor: CGDisplayBlendFraction = 1.0;
+pub const kCGMaxDisplayReservationInterval: CGDisplayReservationInterval = 15.0;
+
+pub type CGCaptureOptions = libc::uint32_t;
+
+pub const kCGCaptureNoOptions: CGCaptureOptions = 0;
+pub const kCGCaptureNoFill: CGCaptureOptions = 1 << 0;
+
+pub type CGWindowLevel = libc::int32_t;
+
+pub type CGGammaValue = libc::c_float;
+
+pub type CGDisplayChangeSummaryFlags = libc::uint32_t;
+
+pub const kCGDisplayBeginConfigurationFlag: CGDisplayChangeSummaryFlags = 1 << 0;
+pub const kCGDisplayMovedFlag: CGDisplayChangeSummaryFlags = 1 << 1;
+pub const kCGDisplaySetMainFlag: CGDisplayChangeSummaryFlags = 1 << 2;
+pub const kCGDisplaySetModeFlag: CGDisplayChangeSummaryFlags = 1 << 3;
+pub const kCGDisplayAddFlag: CGDisplayChangeSummaryFlags = 1 << 4;
+pub const kCGDisplayRemoveFlag: CGDisplayChangeSummaryFlags = 1 << 5;
+pub const kCGDisplayEnabledFlag: CGDisplayChangeSummaryFlags = 1 << 8;
+pub const kCGDisplayDisabledFlag: CGDisplayChangeSummaryFlags = 1 << 9;
+pub const kCGDisplayMirrorFlag: CGDisplayChangeSummaryFlags = 1 << 10;
+pub const kCGDisplayUnMirrorFlag: CGDisplayChangeSummaryFlags = 1 << 11;
+pub const kCGDisplayDesktopShapeChangedFlag: CGDisplayChangeSummaryFlags = 1 << 12;
+
+pub type CGDisplayReconfigurationCallBack = unsafe extern "C" fn(
+    display: CGDirectDisplayID,
+    flags: CGDisplayChangeSummaryFlags,
+    user_info: *mut libc::c_void,
+);
+
+pub const IO1BitIndexedPixels: &str = "P";
+pub const IO2BitIndexedPixels: &str = "PP";
+pub const IO4BitIndexedPixels: &str = "PPPP";
+pub const IO8BitIndexedPixels: &str = "PPPPPPPP";
+pub const IO16BitDirectPixels: &str = "-RRRRRGGGGGBBBBB";
+pub const IO32BitDirectPixels: &str = "--------RRRRRRRRGGGGGGGGBBBBBBBB";
+pub const kIO30BitDirectPixels: &str = "--RRRRRRRRRRGGGGGGGGGGBBBBBBBBBB";
+pub const kIO64BitDirectPixels: &str = "-16R16G16B16";
+pub const kIO16BitFloatPixels: &str = "-16FR16FG16FB16";
+pub const kIO32BitFloatPixels: &str = "-32FR32FG32FB32";
+
+pub const kDisplayModeValidFlag: u32 = 0x00000001;
+pub const kDisplayModeSafeFlag: u32 = 0x00000002;
+pub const kDisplayModeDefaultFlag: u32 = 0x00000004;
+pub const kDisplayModeAlwaysShowFlag: u32 = 0x00000008;
+pub const kDisplayModeNeverShowFlag: u32 = 0x00000080;
+pub const kDisplayModeNotResizeFlag: u32 = 0x00000010;
+pub const kDisplayModeRequiresPanFlag: u32 = 0x00000020;
+pub const kDisplayModeInterlacedFlag: u32 = 0x00000040;
+pub const kDisplayModeStretchedFlag: u32 = 0x00000800;
+pub const kDisplayModeTelevisionFlag: u32 = 0x00100000;
+pub const kDisplayModeValidForMirroringFlag: u32 = 0x00200000;
+pub const kDisplayModeNativeFlag: u32 = 0x02000000;
+
+bitflags! {
+    /// Which windows a window list contains.
+    #[repr(transparent)]
+    pub struct CGWindowListOption: u32 {
+        const kCGWindowListOptionAll = 0;
+        const kCGWindowListOptionOnScreenOnly = 1 << 0;
+        const kCGWindowListOptionOnScreenAboveWindow = 1 << 1;
+        const kCGWindowListOptionOnScreenBelowWindow = 1 << 2;
+        const kCGWindowListOptionIncludingWindow = 1 << 3;
+        const kCGWindowListExcludeDesktopElements = 1 << 4;
+    }
+}
+
+impl From<u32> for CGWindowListOption {
+    /// Keeps bits without a named flag, so that options newer than this
+    /// crate are passed through unchanged.
+    #[inline]
+    fn from(bits: u32) -> CGWindowListOption {
+        unsafe { CGWindowListOption::from_bits_unchecked(bits) }
+    }
+}
+
+pub const kCGWindowListOptionAll: CGWindowListOption =
+    CGWindowListOption::kCGWindowListOptionAll;
+pub const kCGWindowListOptionOnScreenOnly: CGWindowListOption =
+    CGWindowListOption::kCGWindowListOptionOnScreenOnly;
+pub const kCGWindowListOptionOnScreenAboveWindow: CGWindowListOption =
+    CGWindowListOption::kCGWindowListOptionOnScreenAboveWindow;
+pub const kCGWindowListOptionOnScreenBelowWindow: CGWindowListOption =
+    CGWindowListOption::kCGWindowListOptionOnScreenBelowWindow;
+pub const kCGWindowListOptionIncludingWindow: CGWindowListOption =
+    CGWindowListOption::kCGWindowListOptionIncludingWindow;
+pub const kCGWindowListExcludeDesktopElements: CGWindowListOption =
+    CGWindowListOption::kCGWindowListExcludeDesktopElements;
+
+bitflags! {
+    /// How the windows of a window list are rendered into an image.
+    #[repr(transparent)]
+    pub struct CGWindowImageOption: u32 {
+        const kCGWindowImageDefault = 0;
+        const kCGWindowImageBoundsIgnoreFraming = 1 << 0;
+        const kCGWindowImageShouldBeOpaque = 1 << 1;
+        const kCGWindowImageOnlyShadows = 1 << 2;
+        const kCGWindowImageBestResolution = 1 << 3;
+        const kCGWindowImageNominalResolution = 1 << 4;
+    }
+}
+
+impl From<u32> for CGWindowImageOption {
+    /// Keeps bits without a named flag, so that options newer than this
+    /// crate are passed through unchanged.
+    #[inline]
+    fn from(bits: u32) -> CGWindowImageOption {
+        unsafe { CGWindowImageOption::from_bits_unchecked(bits) }
+    }
+}
+
+pub const kCGWindowImageDefault: CGWindowImageOption =
+    CGWindowImageOption::kCGWindowImageDefault;
+pub const kCGWindowImageBoundsIgnoreFraming: CGWindowImageOption =
+    CGWindowImageOption::kCGWindowImageBoundsIgnoreFraming;
+pub const kCGWindowImageShouldBeOpaque: CGWindowImageOption =
+    CGWindowImageOption::kCGWindowImageShouldBeOpaque;
+pub const kCGWindowImageOnlyShadows: CGWindowImageOption =
+    CGWindowImageOption::kCGWindowImageOnlyShadows;
+pub const kCGWindowImageBestResolution: CGWindowImageOption =
+    CGWindowImageOption::kCGWindowImageBestResolution;
+pub const kCGWindowImageNominalResolution: CGWindowImageOption =
+    CGWindowImageOption::kCGWindowImageNominalResolution;
 
 pub use core_foundation::dictionary::{ CFDictionary, CFDictionaryRef, CFDictionaryGetValueIfPresent };
 pub use core_foundation::array::{ CFArray, CFArrayRef };
 pub use core_foundation::array::{ CFArrayGetCount, CFArrayGetValueAtIndex };
+use core_foundation::array::CFArrayCreate;
+use core_foundation::base::kCFAllocatorDefault;
 pub use core_foundation::base::{  CFIndex, CFRelease, CFTypeRef };
 
+/// The properties of a window, decoded from one of the dictionaries returned
+/// by `CGDisplay::window_list_info`.
+#[derive(Clone, Debug)]
+pub struct CGWindowInfo {
+    pub id: CGWindowID,
+    pub owner_pid: i32,
+    pub owner_name: Option<String>,
+    pub name: Option<String>,
+    pub layer: i32,
+    pub bounds: CGRect,
+    pub alpha: f64,
+    /// An estimate of the memory used by the window and its backing store, in
+    /// bytes.
+    pub memory_usage: i64,
+    pub is_onscreen: bool,
+}
+
+impl CGWindowInfo {
+    /// Decodes a window information dictionary. Returns `None` if a required
+    /// key (the window number, owner PID, layer or bounds) is missing.
+    pub fn from_dictionary(dict: &CFDictionary<CFString, CFType>) -> Option<CGWindowInfo> {
+        unsafe {
+            let number = |key| {
+                dict.find(key)
+                    .and_then(|value| value.downcast::<CFNumber>())
+                    .and_then(|number| number.to_i64())
+            };
+            let string = |key| {
+                dict.find(key)
+                    .and_then(|value| value.downcast::<CFString>())
+                    .map(|string| string.to_string())
+            };
+            let bounds = dict.find(kCGWindowBounds)
+                .and_then(|value| value.clone().downcast_into::<CFDictionary>())
+                .and_then(|bounds| CGRect::from_dict_representation(&bounds));
+            Some(CGWindowInfo {
+                id: number(kCGWindowNumber)? as CGWindowID,
+                owner_pid: number(kCGWindowOwnerPID)? as i32,
+                owner_name: string(kCGWindowOwnerName),
+                name: string(kCGWindowName),
+                layer: number(kCGWindowLayer)? as i32,
+                bounds: bounds?,
+                alpha: dict.find(kCGWindowAlpha)
+                           .and_then(|value| value.downcast::<CFNumber>())
+                           .and_then(|alpha| alpha.to_f64())
+                           .unwrap_or(1.),
+                memory_usage: number(kCGWindowMemoryUsage).unwrap_or(0),
+                // Only present for windows that are on screen, and a
+                // `CFBoolean` rather than a number.
+                is_onscreen: dict.get_bool(kCGWindowIsOnscreen).unwrap_or(false),
+            })
+        }
+    }
+}
+
+/// An iterator over the windows of a window list, see
+/// `CGDisplay::window_info_iter`.
+///
+/// Windows whose information lacks a required key are skipped.
+pub struct CGWindowInfoIter {
+    windows: Option<CFArray>,
+    index: CFIndex,
+}
+
+impl Iterator for CGWindowInfoIter {
+    type Item = CGWindowInfo;
+
+    fn next(&mut self) -> Option<CGWindowInfo> {
+        let windows = match self.windows {
+            Some(ref windows) => windows,
+            None => return None,
+        };
+        while self.index < windows.len() {
+            let dict: CFDictionary<CFString, CFType> = unsafe {
+                let dict = CFArrayGetValueAtIndex(windows.as_concrete_TypeRef(), self.index);
+                TCFType::wrap_under_get_rule(dict as CFDictionaryRef)
+            };
+            self.index += 1;
+            if let Some(info) = CGWindowInfo::from_dictionary(&dict) {
+                return Some(info);
+            }
+        }
+        None
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct CGDisplay {
     pub id: CGDirectDisplayID,
 }
 
+/// A capture of a display, together with what's needed to map its pixels back
+/// to the global display coordinate space.
+#[derive(Clone)]
+pub struct CGDisplayImage {
+    pub image: CGImage,
+    /// The number of image pixels per point.
+    pub scale_factor: f64,
+    /// The captured area in the global display coordinate space.
+    pub bounds: CGRect,
+}
+
+impl CGDisplayImage {
+    /// Converts a position in the image, in pixels from its top-left corner,
+    /// to the global display coordinate space.
+    pub fn pixel_to_global(&self, pixel: CGPoint) -> CGPoint {
+        let scale = self.scale_factor as CGFloat;
+        CGPoint::new(self.bounds.origin.x + pixel.x / scale,
+                     self.bounds.origin.y + pixel.y / scale)
+    }
+}
+
 foreign_type! {
     #[doc(hidden)]
     type CType = ::sys::CGDisplayMode;
@@ -92,6 +340,71 @@ impl CGDisplay {
         }
     }
 
+    /// Returns the color space of a display, or `None` if it could not be
+    /// obtained.
+    #[inline]
+    pub fn color_space(&self) -> Option<CGColorSpace> {
+        unsafe {
+            let color_space_ref = CGDisplayCopyColorSpace(self.id);
+            if !color_space_ref.is_null() {
+                Some(CGColorSpace::from_ptr(color_space_ref))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the modes the display supports.
+    ///
+    /// `options` may contain `kCGDisplayShowDuplicateLowResolutionModes` set
+    /// to true to also list the low-resolution duplicates of HiDPI modes.
+    pub fn all_display_modes(&self, options: Option<&CFDictionary<CFString, CFType>>)
+                             -> Vec<CGDisplayMode> {
+        let options = options.map_or(ptr::null(), |options| options.as_concrete_TypeRef());
+        let array_ref = unsafe { CGDisplayCopyAllDisplayModes(self.id, options) };
+        if array_ref.is_null() {
+            return vec![];
+        }
+        let modes: CFArray = unsafe { TCFType::wrap_under_create_rule(array_ref) };
+        modes.iter().map(|mode| {
+            unsafe { CGDisplayModeRef::from_ptr(*mode as ::sys::CGDisplayModeRef).to_owned() }
+        }).collect()
+    }
+
+    /// Switches the display to `mode` for the login session.
+    pub fn set_display_mode(&self, mode: &CGDisplayMode) -> Result<(), CGError> {
+        let result = unsafe { CGDisplaySetDisplayMode(self.id, mode.as_ptr(), ptr::null()) };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Switches the display to `mode`, fading all displays to black while the
+    /// change is applied and back in afterwards.
+    ///
+    /// If a fade reservation can't be acquired the mode is switched without
+    /// fading. The change lasts for the login session.
+    pub fn set_display_mode_with_fade(&self, mode: &CGDisplayMode) -> Result<(), CGError> {
+        const FADE_SECONDS: CGDisplayFadeInterval = 0.3;
+
+        let reservation = DisplayFadeReservation::acquire(kCGMaxDisplayReservationInterval).ok();
+        if let Some(ref reservation) = reservation {
+            let _ = reservation.fade_out(FADE_SECONDS);
+        }
+
+        let result = DisplayConfigTransaction::begin().and_then(|transaction| {
+            try!(transaction.configure_display_mode(self, mode));
+            transaction.complete(kCGConfigureForSession)
+        });
+
+        if let Some(ref reservation) = reservation {
+            let _ = reservation.fade_in(FADE_SECONDS);
+        }
+        result
+    }
+
     /// Returns an image containing the contents of the specified display.
     #[inline]
     pub fn image(&self) -> Option<CGImage> {
@@ -105,6 +418,112 @@ impl CGDisplay {
         }
     }
 
+    /// Returns an image containing the contents of the specified display along
+    /// with its scale factor and bounds, so that pixels of the image can be
+    /// mapped back to screen coordinates.
+    pub fn image_with_metadata(&self) -> Option<CGDisplayImage> {
+        self.image().map(|image| {
+            CGDisplayImage {
+                image: image,
+                scale_factor: self.scale_factor(),
+                bounds: self.bounds(),
+            }
+        })
+    }
+
+    /// Returns the bitmap info and color space to allocate 8-bit capture
+    /// buffers (e.g. bitmap contexts) for the display with, so that captures
+    /// can be copied into them without any conversion.
+    ///
+    /// Captures of a display are 32-bit BGRA, i.e. premultiplied alpha first
+    /// in little-endian byte order, in the display's color space.
+    pub fn preferred_capture_format(&self) -> (u32, CGColorSpace) {
+        (kCGImageAlphaPremultipliedFirst | kCGBitmapByteOrder32Little,
+         CGColorSpace::for_display(self))
+    }
+
+    /// Returns the number of pixels per point of the display's current mode,
+    /// e.g. 2 for a Retina display, or 1 if the mode can't be obtained.
+    pub fn scale_factor(&self) -> f64 {
+        match self.display_mode() {
+            Some(ref mode) if mode.width() > 0 => {
+                mode.pixel_width() as f64 / mode.width() as f64
+            }
+            _ => 1.,
+        }
+    }
+
+    /// Returns `rect`, given in display-local coordinates, clamped to the
+    /// bounds of the display.
+    ///
+    /// The result is `CGRectNull` if `rect` lies entirely outside the display.
+    #[inline]
+    pub fn clamped_capture_rect(&self, rect: CGRect) -> CGRect {
+        let display_rect = CGRect::new(&CGPoint::new(0., 0.), &self.bounds().size);
+        rect.intersection(&display_rect)
+    }
+
+    /// Returns an image containing the contents of a portion of the specified
+    /// display.
+    ///
+    /// `rect` is in display-local coordinates and is clamped to the display
+    /// bounds first (see `clamped_capture_rect`), so the image never extends
+    /// past the edges of the display. Returns `None` if nothing of `rect` is
+    /// on the display.
+    #[inline]
+    pub fn image_for_rect(&self, rect: CGRect) -> Option<CGImage> {
+        let rect = self.clamped_capture_rect(rect);
+        if rect.is_empty() {
+            return None;
+        }
+        unsafe {
+            let image_ref = CGDisplayCreateImageForRect(self.id, rect);
+            if !image_ref.is_null() {
+                Some(CGImage::from_ptr(image_ref))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns an image containing the contents of the specified display with
+    /// the mouse cursor drawn on top, as `image` does not include it.
+    ///
+    /// If the cursor image isn't available the plain display image is
+    /// returned.
+    pub fn image_with_cursor(&self) -> Option<CGImage> {
+        let image = match self.image() {
+            Some(image) => image,
+            None => return None,
+        };
+        let cursor = match CGSCursor::current() {
+            Some(cursor) => cursor,
+            None => return Some(image),
+        };
+
+        let (width, height) = (image.width(), image.height());
+        let bounds = self.bounds();
+        // Display images are in pixels, whereas the cursor geometry is in points.
+        let scale = width as CGFloat / bounds.size.width;
+        let location = CGDisplay::mouse_location();
+        let x = (location.x - bounds.origin.x - cursor.hot_spot.x) * scale;
+        let y = (location.y - bounds.origin.y - cursor.hot_spot.y) * scale;
+        let size = CGSize::new(cursor.size.width * scale, cursor.size.height * scale);
+
+        let context = CGContext::create_bitmap_context(None, width, height, 8, 0,
+                                                       &CGColorSpace::create_device_rgb(),
+                                                       kCGImageAlphaPremultipliedFirst |
+                                                       kCGBitmapByteOrder32Little);
+        let full = CGRect::new(&CGPoint::new(0., 0.),
+                               &CGSize::new(width as CGFloat, height as CGFloat));
+        context.draw_image(full, &image);
+        // Flip from the top-left origin of display space to the context's bottom-left origin.
+        let cursor_rect = CGRect::new(&CGPoint::new(x, height as CGFloat - y - size.height),
+                                      &size);
+        context.draw_image(cursor_rect, &cursor.image);
+        context.create_image()
+    }
+
     /// Returns a composite image based on a dynamically generated list of
     /// windows.
     #[inline]
@@ -145,6 +564,61 @@ impl CGDisplay {
         }
     }
 
+    /// Flattens window images into one image covering the union of their
+    /// rects, for previews of several windows.
+    ///
+    /// Each entry is an image, its rect in the global display coordinate space
+    /// (origin at the top left, as in window bounds) and its opacity. Entries
+    /// are drawn in order, so later ones end up on top. One point maps to one
+    /// pixel of the result. Returns `None` if `window_images` is empty.
+    pub fn composite_windows(window_images: &[(CGImage, CGRect, f64)]) -> Option<CGImage> {
+        let bounds = window_images.iter().fold(unsafe { CGRectNull }, |bounds, window| {
+            bounds.union(&window.1)
+        });
+        if window_images.is_empty() || bounds.is_empty() {
+            return None;
+        }
+        let context = CGContext::create_argb_bitmap(bounds.size.width.ceil() as usize,
+                                                    bounds.size.height.ceil() as usize);
+        let height = context.height() as CGFloat;
+        for &(ref image, rect, alpha) in window_images {
+            // Flip from the top-left origin of window bounds to the bottom-left
+            // origin of the context.
+            let origin = CGPoint::new(rect.origin.x - bounds.origin.x,
+                                      height - (rect.origin.y - bounds.origin.y) -
+                                          rect.size.height);
+            context.draw_image_with_alpha(CGRect::new(&origin, &rect.size), image,
+                                          alpha as CGFloat);
+        }
+        context.create_image()
+    }
+
+    /// Returns a composite image of the area `bounds` of all on-screen
+    /// windows, except those owned by the process `own_pid`.
+    ///
+    /// This lets a screenshot tool leave its own overlay windows out of the
+    /// shot.
+    pub fn capture_all_windows_excluding(
+        own_pid: i32,
+        bounds: CGRect,
+        image_option: CGWindowImageOption,
+    ) -> Option<CGImage> {
+        let windows: Vec<*const libc::c_void> =
+            CGDisplay::window_infos(kCGWindowListOptionOnScreenOnly, None)
+                .into_iter()
+                .filter(|window| window.owner_pid != own_pid)
+                .map(|window| window.id as usize as *const libc::c_void)
+                .collect();
+        // The array holds window IDs, not CF objects, so it has no callbacks.
+        let windows: CFArray = unsafe {
+            TCFType::wrap_under_create_rule(CFArrayCreate(kCFAllocatorDefault,
+                                                          windows.as_ptr(),
+                                                          windows.len() as CFIndex,
+                                                          ptr::null()))
+        };
+        CGDisplay::screenshot_from_windows(bounds, windows, image_option)
+    }
+
     /// Generates and returns information about the selected windows in the
     /// current user session.
     pub fn window_list_info(
@@ -160,6 +634,92 @@ impl CGDisplay {
         }
     }
 
+    /// Returns an iterator over the decoded information about the selected
+    /// windows in the current user session; see `window_list_info`.
+    pub fn window_info_iter(
+        option: CGWindowListOption,
+        relative_to_window: Option<CGWindowID>,
+    ) -> CGWindowInfoIter {
+        CGWindowInfoIter {
+            windows: CGDisplay::window_list_info(option, relative_to_window),
+            index: 0,
+        }
+    }
+
+    /// Returns the decoded information about the selected windows in the
+    /// current user session; see `window_list_info`.
+    pub fn window_infos(
+        option: CGWindowListOption,
+        relative_to_window: Option<CGWindowID>,
+    ) -> Vec<CGWindowInfo> {
+        CGDisplay::window_info_iter(option, relative_to_window).collect()
+    }
+
+    /// Calls `f` with the display and a summary of the changes each time a
+    /// display is reconfigured, e.g. plugged in, removed or rearranged.
+    ///
+    /// `f` is called once for each affected display before the change, with
+    /// `kCGDisplayBeginConfigurationFlag` set, and once after it. Like all
+    /// reconfiguration callbacks, it is only called on the main thread, while
+    /// its run loop is running, so it must be `Send`. It stays registered until
+    /// the returned observer is dropped.
+    pub fn register_reconfiguration_callback<F>(f: F)
+                                                -> Result<CGDisplayReconfigurationObserver,
+                                                          CGError>
+                                                where F: FnMut(CGDisplay,
+                                                               CGDisplayChangeSummaryFlags) +
+                                                         Send + 'static {
+        let mut callback: Box<ReconfigurationCallback> = Box::new(Box::new(f));
+        let result = unsafe {
+            let user_info = &mut *callback as *mut ReconfigurationCallback as *mut libc::c_void;
+            CGDisplayRegisterReconfigurationCallback(reconfiguration_callback, user_info)
+        };
+        if result == 0 {
+            Ok(CGDisplayReconfigurationObserver { callback: callback })
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Calls `f` once each time the display configuration has finished
+    /// changing, rather than for every display before and after the change as
+    /// `register_reconfiguration_callback` would.
+    ///
+    /// Like all reconfiguration callbacks, `f` is only called on the main
+    /// thread, while its run loop is running. It stays registered until the
+    /// returned observer is dropped.
+    pub fn on_settled_reconfiguration<F>(f: F)
+                                         -> Result<CGDisplayReconfigurationObserver, CGError>
+                                         where F: FnMut() + Send + 'static {
+        let mut state = SettledReconfiguration {
+            pending: vec![],
+            callback: Box::new(f),
+        };
+        CGDisplay::register_reconfiguration_callback(move |display, flags| {
+            state.handle(display.id, flags)
+        })
+    }
+
+    /// Returns the active displays along with a channel of subsequent changes
+    /// to them, so that no change between the snapshot and the subscription
+    /// is missed.
+    ///
+    /// Events are only delivered while the main thread's run loop is running,
+    /// and until the returned observer is dropped.
+    pub fn watch_displays() -> Result<(Vec<CGDisplay>,
+                                       Receiver<DisplayEvent>,
+                                       CGDisplayReconfigurationObserver),
+                                      CGError> {
+        let (sender, receiver) = mpsc::channel();
+        let observer = try!(CGDisplay::register_reconfiguration_callback(move |display, flags| {
+            for event in DisplayEvent::from_flags(display.id, flags) {
+                let _ = sender.send(event);
+            }
+        }));
+        let displays = CGDisplay::active_displays().unwrap_or(vec![]);
+        Ok((displays.into_iter().map(CGDisplay::new).collect(), receiver, observer))
+    }
+
     /// Returns a Boolean value indicating whether a display is active.
     #[inline]
     pub fn is_active(&self) -> bool {
@@ -275,6 +835,114 @@ impl CGDisplay {
         unsafe { CGDisplayModelNumber(self.id) }
     }
 
+    /// Returns the `(vendor, model, serial)` numbers of the display monitor.
+    #[inline]
+    pub fn hardware_id(&self) -> (u32, u32, u32) {
+        (self.vendor_number(), self.model_number(), self.serial_number())
+    }
+
+    /// Returns a 64-bit hash of `hardware_id`, stable across processes and
+    /// sessions, that can be used to recognize a particular monitor.
+    ///
+    /// Many monitors report a serial number of 0, so two monitors of the same
+    /// make and model may share a fingerprint; it is not guaranteed to be
+    /// unique.
+    pub fn hardware_fingerprint(&self) -> u64 {
+        let (vendor, model, serial) = self.hardware_id();
+        // 64-bit FNV-1a over the three numbers in little-endian order.
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for value in &[vendor, model, serial] {
+            for shift in 0..4 {
+                hash ^= ((value >> (shift * 8)) & 0xff) as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        hash
+    }
+
+    /// Returns the number of entries of the display's gamma tables.
+    #[inline]
+    pub fn gamma_table_capacity(&self) -> u32 {
+        unsafe { CGDisplayGammaTableCapacity(self.id) }
+    }
+
+    /// Sets the gamma function of each of the red, green and blue channels,
+    /// given as `(min, max, gamma)`, so that a channel value `v` is displayed
+    /// as `min + (max - min) * v.powf(gamma)`.
+    pub fn set_transfer_by_formula(&self,
+                                   red: (CGGammaValue, CGGammaValue, CGGammaValue),
+                                   green: (CGGammaValue, CGGammaValue, CGGammaValue),
+                                   blue: (CGGammaValue, CGGammaValue, CGGammaValue))
+                                   -> Result<(), CGError> {
+        let result = unsafe {
+            CGSetDisplayTransferByFormula(self.id,
+                                          red.0, red.1, red.2,
+                                          green.0, green.1, green.2,
+                                          blue.0, blue.1, blue.2)
+        };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Sets the gamma tables of the display, which map evenly spaced channel
+    /// values to displayed values between 0 and 1, e.g. to dim the display or
+    /// shift its color temperature.
+    ///
+    /// The tables must have the same length, and are interpolated if shorter
+    /// than `gamma_table_capacity`. The change lasts until the process exits
+    /// or `restore_color_sync_settings` is called.
+    pub fn set_transfer_by_table(&self,
+                                 red: &[CGGammaValue],
+                                 green: &[CGGammaValue],
+                                 blue: &[CGGammaValue])
+                                 -> Result<(), CGError> {
+        if red.len() != green.len() || red.len() != blue.len() {
+            return Err(CGError::IllegalArgument);
+        }
+        let result = unsafe {
+            CGSetDisplayTransferByTable(self.id, red.len() as u32,
+                                        red.as_ptr(), green.as_ptr(), blue.as_ptr())
+        };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Returns the red, green and blue gamma tables of the display.
+    pub fn transfer_by_table(&self)
+                             -> Result<(Vec<CGGammaValue>, Vec<CGGammaValue>, Vec<CGGammaValue>),
+                                       CGError> {
+        let capacity = self.gamma_table_capacity();
+        let mut red = vec![0.; capacity as usize];
+        let mut green = vec![0.; capacity as usize];
+        let mut blue = vec![0.; capacity as usize];
+        let mut count = 0;
+        let result = unsafe {
+            CGGetDisplayTransferByTable(self.id, capacity,
+                                        red.as_mut_ptr(), green.as_mut_ptr(), blue.as_mut_ptr(),
+                                        &mut count)
+        };
+        if result != 0 {
+            return Err(CGError::from_code(result));
+        }
+        red.truncate(count as usize);
+        green.truncate(count as usize);
+        blue.truncate(count as usize);
+        Ok((red, green, blue))
+    }
+
+    /// Restores the gamma tables of all displays to the values in the user's
+    /// ColorSync settings.
+    #[inline]
+    pub fn restore_color_sync_settings() {
+        unsafe { CGDisplayRestoreColorSyncSettings() }
+    }
+
     /// Returns the display height in pixel units.
     #[inline]
     pub fn pixels_high(&self) -> u64 {
@@ -297,7 +965,147 @@ impl CGDisplay {
         if result == 0 {
             Ok(buf)
         } else {
-            Err(result)
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Returns the active display whose current mode has the highest refresh
+    /// rate, or `None` if there are no active displays.
+    pub fn fastest_display() -> Option<CGDisplay> {
+        let displays = CGDisplay::active_displays().unwrap_or(vec![]);
+        displays.into_iter()
+                .map(CGDisplay::new)
+                .map(|display| (display, display.refresh_rate()))
+                .fold(None, |fastest: Option<(CGDisplay, f64)>, (display, rate)| {
+                    match fastest {
+                        Some((_, fastest_rate)) if fastest_rate >= rate => fastest,
+                        _ => Some((display, rate)),
+                    }
+                })
+                .map(|(display, _)| display)
+    }
+
+    /// Returns the refresh rate of the display's current mode, in Hz.
+    ///
+    /// Built-in and other LCD panels often report a rate of 0 for their
+    /// modes; the nominal rate of a CoreVideo display link is used for those
+    /// instead. Returns 0 if neither is available.
+    pub fn refresh_rate(&self) -> f64 {
+        let rate = self.display_mode().map_or(0., |mode| mode.refresh_rate());
+        if rate > 0. {
+            return rate;
+        }
+        unsafe {
+            let mut link = ptr::null_mut();
+            if CVDisplayLinkCreateWithCGDisplay(self.id, &mut link) != 0 || link.is_null() {
+                return 0.;
+            }
+            let period = CVDisplayLinkGetNominalOutputVideoRefreshPeriod(link);
+            CVDisplayLinkRelease(link);
+            if period.flags & kCVTimeIsIndefinite != 0 || period.timeValue == 0 {
+                return 0.;
+            }
+            period.timeScale as f64 / period.timeValue as f64
+        }
+    }
+
+    /// Blocks until the display's next vertical blank, so that a capture can
+    /// be lined up with a frame boundary.
+    ///
+    /// This polls the beam position of the display. Most flat panels don't
+    /// report one (it always reads 0); for those this falls back to
+    /// busy-waiting for one frame period at the display's refresh rate.
+    /// Returns `CGError::IllegalArgument` if the display isn't active.
+    pub fn wait_for_vblank(&self) -> Result<(), CGError> {
+        if !self.is_active() {
+            return Err(CGError::IllegalArgument);
+        }
+        let rate = self.refresh_rate();
+        let frame = Duration::from_secs_f64(1. / if rate > 0. { rate } else { 60. });
+        let deadline = Instant::now() + frame;
+        let height = self.pixels_high() as u32;
+        let mut last = unsafe { CGDisplayBeamPosition(self.id) };
+        while Instant::now() < deadline {
+            let position = unsafe { CGDisplayBeamPosition(self.id) };
+            // The beam either moves past the last visible line or, if the
+            // blanking lines aren't reported, wraps back to the top.
+            if (position >= height && last < height) || position < last {
+                break;
+            }
+            last = position;
+            thread::yield_now();
+        }
+        Ok(())
+    }
+
+    /// Returns the smallest rect in the global display coordinate space that
+    /// contains all active displays, i.e. the bounds of the whole desktop.
+    ///
+    /// The origin may be negative when displays are arranged above or to the
+    /// left of the main display. Returns `CGRectNull` if the active displays
+    /// can't be listed.
+    pub fn total_desktop_bounds() -> CGRect {
+        let displays = CGDisplay::active_displays().unwrap_or(vec![]);
+        displays.into_iter().fold(unsafe { CGRectNull }, |bounds, id| {
+            bounds.union(&CGDisplay::new(id).bounds())
+        })
+    }
+
+    /// Returns the displays that are online, i.e. connected, including those
+    /// that are asleep or in a hardware mirror set and so aren't active.
+    pub fn online_displays() -> Result<Vec<CGDirectDisplayID>, CGError> {
+        let mut count = 0;
+        let result = unsafe { CGGetOnlineDisplayList(0, ptr::null_mut(), &mut count) };
+        if result != 0 {
+            return Err(CGError::from_code(result));
+        }
+        let mut buf: Vec<CGDirectDisplayID> = vec![0; count as usize];
+        let result = unsafe { CGGetOnlineDisplayList(count, buf.as_mut_ptr(), &mut count) };
+        if result == 0 {
+            buf.truncate(count as usize);
+            Ok(buf)
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Returns the online displays whose bounds contain `point`, given in the
+    /// global display coordinate space.
+    pub fn displays_with_point(point: CGPoint) -> Result<Vec<CGDirectDisplayID>, CGError> {
+        let mut count = 0;
+        let result = unsafe { CGGetDisplaysWithPoint(point, 0, ptr::null_mut(), &mut count) };
+        if result != 0 {
+            return Err(CGError::from_code(result));
+        }
+        let mut buf: Vec<CGDirectDisplayID> = vec![0; count as usize];
+        let result = unsafe {
+            CGGetDisplaysWithPoint(point, count, buf.as_mut_ptr(), &mut count)
+        };
+        if result == 0 {
+            buf.truncate(count as usize);
+            Ok(buf)
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Returns the online displays whose bounds intersect `rect`, given in the
+    /// global display coordinate space.
+    pub fn displays_with_rect(rect: CGRect) -> Result<Vec<CGDirectDisplayID>, CGError> {
+        let mut count = 0;
+        let result = unsafe { CGGetDisplaysWithRect(rect, 0, ptr::null_mut(), &mut count) };
+        if result != 0 {
+            return Err(CGError::from_code(result));
+        }
+        let mut buf: Vec<CGDirectDisplayID> = vec![0; count as usize];
+        let result = unsafe {
+            CGGetDisplaysWithRect(rect, count, buf.as_mut_ptr(), &mut count)
+        };
+        if result == 0 {
+            buf.truncate(count as usize);
+            Ok(buf)
+        } else {
+            Err(CGError::from_code(result))
         }
     }
 
@@ -309,10 +1117,106 @@ impl CGDisplay {
         if result == 0 {
             Ok(count as u32)
         } else {
-            Err(result)
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Captures the display for exclusive use by this process, e.g. for a
+    /// full-screen game. Other apps no longer draw to it until it is released,
+    /// and it is filled with black.
+    #[inline]
+    pub fn capture(&self) -> Result<(), CGError> {
+        let result = unsafe { CGDisplayCapture(self.id) };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Captures the display like `capture`; with `kCGCaptureNoFill` the
+    /// display isn't filled with black.
+    #[inline]
+    pub fn capture_with_options(&self, options: CGCaptureOptions) -> Result<(), CGError> {
+        let result = unsafe { CGDisplayCaptureWithOptions(self.id, options) };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Releases a captured display.
+    #[inline]
+    pub fn release(&self) -> Result<(), CGError> {
+        let result = unsafe { CGDisplayRelease(self.id) };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Returns a context for drawing directly to the display, or `None` if the
+    /// display isn't captured by this process.
+    ///
+    /// The context stays valid until the display is released or its mode
+    /// changes.
+    pub fn drawing_context(&self) -> Option<CGContext> {
+        unsafe {
+            let context = CGDisplayGetDrawingContext(self.id);
+            if !context.is_null() {
+                Some(CGContextRef::from_ptr(context).to_owned())
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Captures all attached displays, see `capture`.
+    #[inline]
+    pub fn capture_all_displays() -> Result<(), CGError> {
+        let result = unsafe { CGCaptureAllDisplays() };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
         }
     }
 
+    /// Releases all captured displays.
+    #[inline]
+    pub fn release_all_displays() -> Result<(), CGError> {
+        let result = unsafe { CGReleaseAllDisplays() };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Returns the level of the window that covers captured displays. Windows
+    /// above this level stay visible while the displays are captured.
+    #[inline]
+    pub fn shielding_window_level() -> CGWindowLevel {
+        unsafe { CGShieldingWindowLevel() }
+    }
+
+    /// Returns the window that covers the display while it is captured.
+    #[inline]
+    pub fn shielding_window_id(&self) -> CGWindowID {
+        unsafe { CGShieldingWindowID(self.id) }
+    }
+
+    /// Returns the current location of the mouse cursor in the global display
+    /// coordinate space, with the origin at the top left of the main display as
+    /// used by `warp_mouse_cursor_position`.
+    pub fn mouse_location() -> CGPoint {
+        let source = CGEventSource::new(CGEventSourceStateID::CombinedSessionState)
+            .expect("failed to create an event source");
+        CGEvent::new(source).expect("failed to create an event").location()
+    }
+
     /// Hides the mouse cursor, and increments the hide cursor count.
     #[inline]
     pub fn hide_cursor(&self) -> Result<(), CGError> {
@@ -320,7 +1224,7 @@ impl CGDisplay {
         if result == 0 {
             Ok(())
         } else {
-            Err(result)
+            Err(CGError::from_code(result))
         }
     }
 
@@ -332,7 +1236,7 @@ impl CGDisplay {
         if result == 0 {
             Ok(())
         } else {
-            Err(result)
+            Err(CGError::from_code(result))
         }
     }
 
@@ -344,10 +1248,23 @@ impl CGDisplay {
         if result == 0 {
             Ok(())
         } else {
-            Err(result)
+            Err(CGError::from_code(result))
         }
     }
 
+    /// Moves the mouse cursor to a point given in the display's logical
+    /// (rotated) coordinate space, relative to the upper-left corner of the
+    /// display as the user sees it.
+    ///
+    /// The point is mapped back through the display's rotation before being
+    /// handed to `move_cursor_to_point`. Rotations that are not a multiple of
+    /// 90 degrees are rounded to the nearest quarter turn.
+    #[inline]
+    pub fn move_cursor_to_point_rotated(&self, logical_point: CGPoint) -> Result<(), CGError> {
+        let point = unrotate_point(logical_point, self.rotation(), self.bounds().size);
+        self.move_cursor_to_point(point)
+    }
+
     /// Moves the mouse cursor without generating events.
     #[inline]
     pub fn warp_mouse_cursor_position(point: CGPoint) -> Result<(), CGError> {
@@ -355,7 +1272,7 @@ impl CGDisplay {
         if result == 0 {
             Ok(())
         } else {
-            Err(result)
+            Err(CGError::from_code(result))
         }
     }
 
@@ -367,9 +1284,329 @@ impl CGDisplay {
         if result == 0 {
             Ok(())
         } else {
-            Err(result)
+            Err(CGError::from_code(result))
+        }
+    }
+}
+
+/// A reservation of the fade hardware, which allows fading all displays, e.g.
+/// to hide the flash of a mode change.
+///
+/// The reservation is released when this is dropped, or by the system once
+/// the interval it was acquired for has passed.
+pub struct DisplayFadeReservation {
+    token: CGDisplayFadeReservationToken,
+}
+
+impl DisplayFadeReservation {
+    /// Reserves the fade hardware for up to `seconds`, which may be at most
+    /// `kCGMaxDisplayReservationInterval`.
+    pub fn acquire(seconds: CGDisplayReservationInterval)
+                   -> Result<DisplayFadeReservation, CGError> {
+        let mut token = kCGDisplayFadeReservationInvalidToken;
+        let result = unsafe { CGAcquireDisplayFadeReservation(seconds, &mut token) };
+        if result == 0 {
+            Ok(DisplayFadeReservation { token: token })
+        } else {
+            Err(CGError::from_code(result))
         }
     }
+
+    /// Fades all displays from `start_blend` to `end_blend` over `duration`
+    /// seconds, where `kCGDisplayBlendNormal` shows the display contents and
+    /// `kCGDisplayBlendSolidColor` shows only the `(red, green, blue)` color.
+    ///
+    /// If `synchronous` is true this only returns once the fade is done.
+    pub fn fade(&self,
+                duration: CGDisplayFadeInterval,
+                start_blend: CGDisplayBlendFraction,
+                end_blend: CGDisplayBlendFraction,
+                (red, green, blue): (f32, f32, f32),
+                synchronous: bool)
+                -> Result<(), CGError> {
+        let result = unsafe {
+            CGDisplayFade(self.token, duration, start_blend, end_blend, red, green, blue,
+                          synchronous as boolean_t)
+        };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Fades all displays to black over `duration` seconds and waits for it.
+    #[inline]
+    pub fn fade_out(&self, duration: CGDisplayFadeInterval) -> Result<(), CGError> {
+        self.fade(duration, kCGDisplayBlendNormal, kCGDisplayBlendSolidColor, (0., 0., 0.), true)
+    }
+
+    /// Fades all displays back in from black over `duration` seconds and
+    /// waits for it.
+    #[inline]
+    pub fn fade_in(&self, duration: CGDisplayFadeInterval) -> Result<(), CGError> {
+        self.fade(duration, kCGDisplayBlendSolidColor, kCGDisplayBlendNormal, (0., 0., 0.), true)
+    }
+}
+
+impl Drop for DisplayFadeReservation {
+    fn drop(&mut self) {
+        unsafe {
+            CGReleaseDisplayFadeReservation(self.token);
+        }
+    }
+}
+
+/// A batch of display configuration changes that are applied together by
+/// `complete`.
+///
+/// Dropping the transaction without completing it cancels the changes.
+pub struct DisplayConfigTransaction {
+    config: CGDisplayConfigRef,
+}
+
+impl DisplayConfigTransaction {
+    /// Starts a new configuration transaction.
+    pub fn begin() -> Result<DisplayConfigTransaction, CGError> {
+        let mut config = ptr::null_mut();
+        let result = unsafe { CGBeginDisplayConfiguration(&mut config) };
+        if result == 0 {
+            Ok(DisplayConfigTransaction { config: config })
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Moves the origin of `display` to `(x, y)` in the global display
+    /// coordinate space. The main display stays at the origin, so the other
+    /// displays are adjusted relative to it.
+    pub fn configure_origin(&self, display: &CGDisplay, x: i32, y: i32) -> Result<(), CGError> {
+        let result = unsafe { CGConfigureDisplayOrigin(self.config, display.id, x, y) };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Switches `display` to `mode`.
+    pub fn configure_display_mode(&self, display: &CGDisplay, mode: &CGDisplayMode)
+                                  -> Result<(), CGError> {
+        let result = unsafe {
+            CGConfigureDisplayWithDisplayMode(self.config, display.id, mode.as_ptr(),
+                                              ptr::null())
+        };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Applies all changes of the transaction at once, for as long as `option`
+    /// says.
+    pub fn complete(self, option: CGConfigureOption) -> Result<(), CGError> {
+        let result = unsafe { CGCompleteDisplayConfiguration(self.config, option) };
+        // The configuration is released by completing it, whether or not that
+        // succeeds.
+        mem::forget(self);
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Discards all changes of the transaction.
+    #[inline]
+    pub fn cancel(self) {
+        drop(self)
+    }
+}
+
+impl Drop for DisplayConfigTransaction {
+    fn drop(&mut self) {
+        unsafe {
+            CGCancelDisplayConfiguration(self.config);
+        }
+    }
+}
+
+/// Captures a display repeatedly and reports which parts of it changed since
+/// the previous capture, e.g. for a screen recorder that only encodes updates.
+pub struct DisplayCapturer {
+    display: CGDisplay,
+    tile_size: usize,
+    previous: Option<CGImage>,
+}
+
+impl DisplayCapturer {
+    /// Creates a capturer for `display` that compares frames in tiles of
+    /// `tile_size` x `tile_size` pixels.
+    pub fn new(display: CGDisplay, tile_size: usize) -> DisplayCapturer {
+        DisplayCapturer {
+            display: display,
+            tile_size: cmp::max(tile_size, 1),
+            previous: None,
+        }
+    }
+
+    /// Returns the most recent capture, if any.
+    pub fn frame(&self) -> Option<&CGImage> {
+        self.previous.as_ref()
+    }
+
+    /// Captures the display and returns the tiles that differ from the
+    /// previous capture, in pixels with the origin at the top-left corner of
+    /// the image. The whole image is dirty the first time, or when its size
+    /// changes. Returns no rects if the display can't be captured.
+    pub fn capture_changed_regions(&mut self) -> Vec<CGRect> {
+        match self.display.image() {
+            Some(image) => self.update(image),
+            None => vec![],
+        }
+    }
+
+    /// Replaces the previous frame with `image` and returns the tiles that
+    /// changed, as `capture_changed_regions` does.
+    pub fn update(&mut self, image: CGImage) -> Vec<CGRect> {
+        let dirty = match self.previous {
+            Some(ref previous) if previous.compatible_with(&image) => {
+                let (previous_data, data) = (previous.data(), image.data());
+                let bytes_per_pixel = image.bits_per_pixel() / 8;
+                self.changed_tiles(previous_data.bytes(), previous.bytes_per_row(),
+                                   data.bytes(), image.bytes_per_row(),
+                                   image.width(), image.height(), bytes_per_pixel)
+            }
+            _ => {
+                vec![CGRect::new(&CGPoint::new(0., 0.),
+                                 &CGSize::new(image.width() as CGFloat,
+                                              image.height() as CGFloat))]
+            }
+        };
+        self.previous = Some(image);
+        dirty
+    }
+
+    fn changed_tiles(&self, old: &[u8], old_stride: usize, new: &[u8], new_stride: usize,
+                     width: usize, height: usize, bytes_per_pixel: usize) -> Vec<CGRect> {
+        let tile = self.tile_size;
+        let mut dirty = vec![];
+        for tile_y in (0..height).step_by(tile) {
+            let tile_height = cmp::min(tile, height - tile_y);
+            for tile_x in (0..width).step_by(tile) {
+                let tile_width = cmp::min(tile, width - tile_x);
+                let start = tile_x * bytes_per_pixel;
+                let end = start + tile_width * bytes_per_pixel;
+                let changed = (tile_y..tile_y + tile_height).any(|y| {
+                    old[y * old_stride + start..y * old_stride + end] !=
+                        new[y * new_stride + start..y * new_stride + end]
+                });
+                if changed {
+                    dirty.push(CGRect::new(&CGPoint::new(tile_x as CGFloat, tile_y as CGFloat),
+                                           &CGSize::new(tile_width as CGFloat,
+                                                        tile_height as CGFloat)));
+                }
+            }
+        }
+        dirty
+    }
+}
+
+/// A change to the set or arrangement of displays, see
+/// `CGDisplay::watch_displays`.
+#[derive(Clone, Copy, Debug)]
+pub enum DisplayEvent {
+    Added(CGDisplay),
+    Removed(CGDisplay),
+    Moved(CGDisplay),
+    ModeChanged(CGDisplay),
+}
+
+impl DisplayEvent {
+    fn from_flags(display: CGDirectDisplayID,
+                  flags: CGDisplayChangeSummaryFlags)
+                  -> Vec<DisplayEvent> {
+        let display = CGDisplay::new(display);
+        let mut events = vec![];
+        if flags & kCGDisplayBeginConfigurationFlag != 0 {
+            return events;
+        }
+        if flags & kCGDisplayAddFlag != 0 {
+            events.push(DisplayEvent::Added(display));
+        }
+        if flags & kCGDisplayRemoveFlag != 0 {
+            events.push(DisplayEvent::Removed(display));
+        }
+        if flags & kCGDisplayMovedFlag != 0 {
+            events.push(DisplayEvent::Moved(display));
+        }
+        if flags & kCGDisplaySetModeFlag != 0 {
+            events.push(DisplayEvent::ModeChanged(display));
+        }
+        events
+    }
+}
+
+type ReconfigurationCallback = Box<FnMut(CGDisplay, CGDisplayChangeSummaryFlags) + Send>;
+
+/// Registration of a callback with `CGDisplay::register_reconfiguration_callback`
+/// or `CGDisplay::on_settled_reconfiguration`. The callback is unregistered when
+/// this is dropped.
+pub struct CGDisplayReconfigurationObserver {
+    callback: Box<ReconfigurationCallback>,
+}
+
+impl Drop for CGDisplayReconfigurationObserver {
+    fn drop(&mut self) {
+        unsafe {
+            let user_info =
+                &mut *self.callback as *mut ReconfigurationCallback as *mut libc::c_void;
+            CGDisplayRemoveReconfigurationCallback(reconfiguration_callback, user_info);
+        }
+    }
+}
+
+unsafe extern "C" fn reconfiguration_callback(display: CGDirectDisplayID,
+                                              flags: CGDisplayChangeSummaryFlags,
+                                              user_info: *mut libc::c_void) {
+    (*(user_info as *mut ReconfigurationCallback))(CGDisplay::new(display), flags)
+}
+
+/// Coalesces the per-display begin and end reconfiguration callbacks of a
+/// single configuration change into one call of `callback`.
+struct SettledReconfiguration {
+    pending: Vec<CGDirectDisplayID>,
+    callback: Box<FnMut() + Send>,
+}
+
+impl SettledReconfiguration {
+    fn handle(&mut self, display: CGDirectDisplayID, flags: CGDisplayChangeSummaryFlags) {
+        if flags & kCGDisplayBeginConfigurationFlag != 0 {
+            if !self.pending.contains(&display) {
+                self.pending.push(display);
+            }
+            return;
+        }
+        self.pending.retain(|&pending| pending != display);
+        if self.pending.is_empty() {
+            (self.callback)();
+        }
+    }
+}
+
+// Counts the color bits of a pixel encoding, see `CGDisplayMode::bit_depth`.
+fn encoding_bit_depth(encoding: &str) -> usize {
+    match encoding {
+        IO32BitDirectPixels => 24,
+        kIO30BitDirectPixels => 30,
+        kIO64BitDirectPixels => 48,
+        kIO16BitFloatPixels => 48,
+        kIO32BitFloatPixels => 96,
+        IO16BitDirectPixels => 15,
+        IO8BitIndexedPixels => 8,
+        _ => 0,
+    }
 }
 
 impl CGDisplayMode {
@@ -397,6 +1634,468 @@ impl CGDisplayMode {
     pub fn refresh_rate(&self) -> f64 {
         unsafe { CGDisplayModeGetRefreshRate(self.as_ptr()) }
     }
+
+    /// Returns the layout of a pixel of the mode, e.g. `IO32BitDirectPixels`.
+    pub fn pixel_encoding(&self) -> CFString {
+        unsafe { CFString::wrap_under_create_rule(CGDisplayModeCopyPixelEncoding(self.as_ptr())) }
+    }
+
+    /// Returns the number of bits of color per pixel of the mode, derived from
+    /// its pixel encoding, or 0 for an unknown encoding. Padding and alpha
+    /// aren't counted, so e.g. `IO32BitDirectPixels` has a depth of 24.
+    pub fn bit_depth(&self) -> usize {
+        encoding_bit_depth(&self.pixel_encoding().to_string())
+    }
+
+    /// Returns the I/O Kit flags of the mode, see the `kDisplayMode*Flag`
+    /// constants.
+    #[inline]
+    pub fn io_flags(&self) -> u32 {
+        unsafe { CGDisplayModeGetIOFlags(self.as_ptr()) }
+    }
+
+    /// Returns the I/O Kit ID of the mode.
+    #[inline]
+    pub fn io_display_mode_id(&self) -> i32 {
+        unsafe { CGDisplayModeGetIODisplayModeID(self.as_ptr()) }
+    }
+
+    /// Returns whether the mode is suitable for the desktop, as opposed to
+    /// e.g. a low resolution only meant for full-screen games.
+    #[inline]
+    pub fn is_usable_for_desktop_gui(&self) -> bool {
+        unsafe { CGDisplayModeIsUsableForDesktopGUI(self.as_ptr()) != 0 }
+    }
+}
+
+impl fmt::Display for CGDisplayMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{} @{}Hz ({}x{})",
+               self.width(), self.height(), self.refresh_rate(),
+               self.pixel_width(), self.pixel_height())
+    }
+}
+
+impl fmt::Debug for CGDisplayMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CGDisplayMode({})", self)
+    }
+}
+
+/// Maps a point in the rotated coordinate space of a display of `logical_size`
+/// back to the unrotated space, for a clockwise rotation of `rotation` degrees.
+fn unrotate_point(point: CGPoint, rotation: f64, logical_size: CGSize) -> CGPoint {
+    let quarter_turns = ((rotation / 90.).round() as i64).rem_euclid(4);
+    let (width, height) = (logical_size.width, logical_size.height);
+    match quarter_turns {
+        1 => CGPoint::new(point.y, width - point.x),
+        2 => CGPoint::new(width - point.x, height - point.y),
+        3 => CGPoint::new(height - point.y, point.x),
+        _ => point,
+    }
+}
+
+#[test]
+fn unrotate_point_identity_test() {
+    let size = CGSize::new(1440., 900.);
+    let point = unrotate_point(CGPoint::new(100., 200.), 0., size);
+    assert_eq!(point.x, 100.);
+    assert_eq!(point.y, 200.);
+
+    let point = unrotate_point(CGPoint::new(100., 200.), 180., size);
+    assert_eq!(point.x, 1340.);
+    assert_eq!(point.y, 700.);
+}
+
+#[test]
+fn unrotate_point_quarter_turn_test() {
+    // The unrotated display is 1440x900, so its rotated bounds are 900x1440.
+    let (width, height) = (1440., 900.);
+    let rotated_size = CGSize::new(height, width);
+    let points = [(0., 0.), (100., 200.), (width, 0.), (0., height), (width, height)];
+    for &(x, y) in &points {
+        // Rotating clockwise by 90 degrees moves the top left corner to the top right.
+        let point = unrotate_point(CGPoint::new(height - y, x), 90., rotated_size);
+        assert_eq!((point.x, point.y), (x, y));
+
+        // Rotating clockwise by 270 degrees moves the top left corner to the bottom left.
+        let point = unrotate_point(CGPoint::new(y, width - x), 270., rotated_size);
+        assert_eq!((point.x, point.y), (x, y));
+    }
+
+    let point = unrotate_point(CGPoint::new(700., 100.), 90., rotated_size);
+    assert_eq!((point.x, point.y), (100., 200.));
+    let point = unrotate_point(CGPoint::new(200., 1340.), 270., rotated_size);
+    assert_eq!((point.x, point.y), (100., 200.));
+}
+
+#[test]
+#[ignore] // needs a rotated display
+fn move_cursor_to_point_rotated_test() {
+    let display = CGDisplay::main();
+    assert!(display.rotation() != 0.);
+    display.move_cursor_to_point_rotated(CGPoint::new(10., 10.)).unwrap();
+}
+
+#[test]
+fn hardware_fingerprint_test() {
+    let display = CGDisplay::main();
+    assert_eq!(display.hardware_fingerprint(), display.hardware_fingerprint());
+}
+
+#[test]
+#[ignore] // switches the mode of the main display
+fn set_display_mode_with_fade_test() {
+    let display = CGDisplay::main();
+    let original = display.display_mode().unwrap();
+    display.set_display_mode_with_fade(&original).unwrap();
+    let current = display.display_mode().unwrap();
+    assert_eq!(current.width(), original.width());
+    assert_eq!(current.height(), original.height());
+}
+
+#[test]
+fn all_display_modes_test() {
+    use core_foundation::boolean::CFBoolean;
+
+    let display = CGDisplay::main();
+    let current = display.display_mode().unwrap();
+    let modes = display.all_display_modes(None);
+    assert!(modes.iter().any(|mode| {
+        mode.width() == current.width() && mode.height() == current.height()
+    }));
+
+    let key = unsafe { CFString::wrap_under_get_rule(kCGDisplayShowDuplicateLowResolutionModes) };
+    let options = CFDictionary::from_CFType_pairs(&[
+        (key, CFBoolean::true_value().as_CFType()),
+    ]);
+    assert!(display.all_display_modes(Some(&options)).len() >= modes.len());
+}
+
+#[test]
+#[ignore] // switches the mode of the main display
+fn set_display_mode_test() {
+    let display = CGDisplay::main();
+    let original = display.display_mode().unwrap();
+    display.set_display_mode(&original).unwrap();
+    assert_eq!(display.display_mode().unwrap().width(), original.width());
+}
+
+#[test]
+fn display_config_transaction_cancel_test() {
+    let display = CGDisplay::main();
+    let bounds = display.bounds();
+    let transaction = DisplayConfigTransaction::begin().unwrap();
+    transaction.configure_display_mode(&display, &display.display_mode().unwrap()).unwrap();
+    transaction.cancel();
+    assert_eq!(display.bounds().size.width, bounds.size.width);
+}
+
+#[test]
+#[ignore] // needs fade hardware and a display session
+fn display_fade_reservation_test() {
+    let reservation = DisplayFadeReservation::acquire(1.).unwrap();
+    // A fade from and to the normal blend leaves the displays unchanged.
+    reservation.fade(0.1, kCGDisplayBlendNormal, kCGDisplayBlendNormal, (0., 0., 0.), true)
+               .unwrap();
+    drop(reservation);
+    // The hardware can be reserved again once the reservation is released.
+    DisplayFadeReservation::acquire(1.).unwrap();
+}
+
+#[test]
+#[ignore] // takes over the main display
+fn capture_test() {
+    let display = CGDisplay::main();
+    assert!(display.drawing_context().is_none());
+    display.capture_with_options(kCGCaptureNoFill).unwrap();
+    assert!(display.shielding_window_id() != kCGNullWindowID);
+    let context = display.drawing_context().unwrap();
+    assert_eq!(context.width(), display.pixels_wide() as usize);
+    assert!(CGDisplay::shielding_window_level() > 0);
+    display.release().unwrap();
+}
+
+#[test]
+#[ignore] // changes the gamma of the main display
+fn transfer_by_table_test() {
+    let display = CGDisplay::main();
+    let (red, green, blue) = display.transfer_by_table().unwrap();
+    assert!(red.len() <= display.gamma_table_capacity() as usize);
+    assert_eq!(red.len(), green.len());
+    display.set_transfer_by_table(&red, &green, &blue).unwrap();
+    assert_eq!(display.set_transfer_by_table(&red, &green, &blue[1..]),
+               Err(CGError::IllegalArgument));
+    CGDisplay::restore_color_sync_settings();
+}
+
+#[test]
+fn display_mode_accessors_test() {
+    let mode = CGDisplay::main().display_mode().unwrap();
+    assert!(mode.is_usable_for_desktop_gui());
+    assert!(mode.io_flags() & kDisplayModeValidFlag != 0);
+    assert!(mode.bit_depth() >= 15, "{}", mode.pixel_encoding());
+}
+
+#[test]
+fn encoding_bit_depth_test() {
+    assert_eq!(encoding_bit_depth(IO32BitDirectPixels), 24);
+    assert_eq!(encoding_bit_depth(kIO30BitDirectPixels), 30);
+    assert_eq!(encoding_bit_depth(kIO64BitDirectPixels), 48);
+    assert_eq!(encoding_bit_depth(kIO16BitFloatPixels), 48);
+    assert_eq!(encoding_bit_depth(kIO32BitFloatPixels), 96);
+    assert_eq!(encoding_bit_depth(IO16BitDirectPixels), 15);
+    assert_eq!(encoding_bit_depth("?"), 0);
+}
+
+#[test]
+fn displays_with_geometry_test() {
+    let main = CGDisplay::main();
+    assert!(CGDisplay::online_displays().unwrap().contains(&main.id));
+
+    let bounds = main.bounds();
+    let center = CGPoint::new(bounds.origin.x + bounds.size.width / 2.,
+                              bounds.origin.y + bounds.size.height / 2.);
+    assert!(CGDisplay::displays_with_point(center).unwrap().contains(&main.id));
+    assert!(CGDisplay::displays_with_rect(bounds).unwrap().contains(&main.id));
+
+    let far_away = CGPoint::new(-1e7, -1e7);
+    assert!(CGDisplay::displays_with_point(far_away).unwrap().is_empty());
+}
+
+#[test]
+fn mouse_location_test() {
+    let location = CGDisplay::mouse_location();
+    assert!(!CGDisplay::displays_with_point(location).unwrap().is_empty());
+}
+
+#[test]
+fn total_desktop_bounds_test() {
+    let total = CGDisplay::total_desktop_bounds();
+    let main = CGDisplay::main().bounds();
+    assert!(total.origin.x <= main.origin.x);
+    assert!(total.origin.y <= main.origin.y);
+    assert!(total.origin.x + total.size.width >= main.origin.x + main.size.width);
+    assert!(total.origin.y + total.size.height >= main.origin.y + main.size.height);
+}
+
+#[test]
+fn display_mode_format_test() {
+    let mode = CGDisplay::main().display_mode().unwrap();
+    let formatted = mode.to_string();
+    assert!(formatted.starts_with(&format!("{}x", mode.width())));
+    assert!(formatted.contains("Hz"));
+    assert!(format!("{:?}", mode).contains(&formatted));
+}
+
+#[test]
+fn wait_for_vblank_test() {
+    let start = Instant::now();
+    CGDisplay::main().wait_for_vblank().unwrap();
+    assert!(start.elapsed() < Duration::from_millis(100));
+}
+
+#[test]
+fn register_reconfiguration_callback_test() {
+    use std::sync::{Arc, Mutex};
+
+    let changes = Arc::new(Mutex::new(vec![]));
+    let recorder = changes.clone();
+    let mut observer = CGDisplay::register_reconfiguration_callback(move |display, flags| {
+        recorder.lock().unwrap().push((display.id, flags))
+    }).unwrap();
+    unsafe {
+        let user_info =
+            &mut *observer.callback as *mut ReconfigurationCallback as *mut libc::c_void;
+        reconfiguration_callback(7, kCGDisplayAddFlag, user_info);
+    }
+    drop(observer);
+    assert_eq!(*changes.lock().unwrap(), vec![(7, kCGDisplayAddFlag)]);
+}
+
+#[test]
+fn settled_reconfiguration_test() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let mut state = SettledReconfiguration {
+        pending: vec![],
+        callback: Box::new(move || { counter.fetch_add(1, Ordering::SeqCst); }),
+    };
+    // A mode change on a two-display setup: every display reports the begin
+    // flag first and then its actual changes.
+    state.handle(1, kCGDisplayBeginConfigurationFlag);
+    state.handle(2, kCGDisplayBeginConfigurationFlag);
+    state.handle(1, kCGDisplaySetModeFlag | kCGDisplayDesktopShapeChangedFlag);
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+    state.handle(2, kCGDisplayMovedFlag | kCGDisplayDesktopShapeChangedFlag);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let observer = CGDisplay::on_settled_reconfiguration(|| {}).unwrap();
+    drop(observer);
+}
+
+#[test]
+fn capture_all_windows_excluding_test() {
+    let own_pid = ::std::process::id() as i32;
+    let bounds = CGDisplay::main().bounds();
+    let image = CGDisplay::capture_all_windows_excluding(own_pid, bounds, kCGWindowImageDefault);
+    assert!(image.is_some());
+}
+
+#[test]
+fn display_capturer_unchanged_test() {
+    let display = CGDisplay::main();
+    let mut capturer = DisplayCapturer::new(display, 32);
+    // Use the same frame twice, as a real display is never guaranteed to be
+    // static between two captures.
+    let frame = display.image_for_rect(CGRect::new(&CGPoint::new(0., 0.),
+                                                   &CGSize::new(100., 100.))).unwrap();
+    assert_eq!(capturer.update(frame.clone()).len(), 1);
+    assert!(capturer.update(frame).is_empty());
+    assert!(capturer.capture_changed_regions().len() >= 1);
+}
+
+#[test]
+fn preferred_capture_format_test() {
+    let display = CGDisplay::main();
+    let (bitmap_info, space) = display.preferred_capture_format();
+    assert!(!space.as_ptr().is_null());
+    // Bitmap context creation fails for invalid combinations.
+    let context = CGContext::create_bitmap_context(None, 16, 16, 8, 0, &space, bitmap_info);
+    assert_eq!(context.bitmap_info(), bitmap_info);
+}
+
+#[test]
+fn composite_windows_test() {
+    let window = CGContext::create_argb_bitmap(10, 10).create_image().unwrap();
+    let first = CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(10., 10.));
+    let second = CGRect::new(&CGPoint::new(20., 5.), &CGSize::new(10., 10.));
+    let composite = CGDisplay::composite_windows(&[(window.clone(), first, 1.),
+                                                   (window, second, 0.5)]).unwrap();
+    assert_eq!(composite.width(), 30);
+    assert_eq!(composite.height(), 15);
+    assert!(CGDisplay::composite_windows(&[]).is_none());
+}
+
+#[test]
+fn watch_displays_test() {
+    let (displays, events, observer) = CGDisplay::watch_displays().unwrap();
+    assert_eq!(displays.len() as u32, CGDisplay::active_display_count().unwrap());
+    assert!(events.try_recv().is_err());
+    drop(observer);
+    assert!(events.recv().is_err());
+}
+
+#[test]
+fn display_event_from_flags_test() {
+    assert!(DisplayEvent::from_flags(1, kCGDisplayBeginConfigurationFlag | kCGDisplayAddFlag)
+        .is_empty());
+    let events = DisplayEvent::from_flags(1, kCGDisplayAddFlag | kCGDisplayMovedFlag);
+    match (events[0], events[1]) {
+        (DisplayEvent::Added(added), DisplayEvent::Moved(moved)) => {
+            assert_eq!(added.id, 1);
+            assert_eq!(moved.id, 1);
+        }
+        _ => panic!("unexpected events {:?}", events),
+    }
+}
+
+#[test]
+fn fastest_display_test() {
+    if CGDisplay::active_display_count().unwrap() > 0 {
+        assert!(CGDisplay::fastest_display().is_some());
+    }
+}
+
+#[test]
+fn image_with_metadata_test() {
+    let display = CGDisplay::main();
+    let capture = display.image_with_metadata().unwrap();
+    assert_eq!(capture.scale_factor, display.scale_factor());
+    assert_eq!(capture.bounds.size.width, display.bounds().size.width);
+
+    let origin = capture.pixel_to_global(CGPoint::new(0., 0.));
+    assert_eq!(origin.x, capture.bounds.origin.x);
+    assert_eq!(origin.y, capture.bounds.origin.y);
+}
+
+#[test]
+fn window_info_from_dictionary_test() {
+    use core_foundation::boolean::CFBoolean;
+
+    let bounds = CFDictionary::from_CFType_pairs(&[
+        (CFString::from_static_string("X"), CFNumber::from(10)),
+        (CFString::from_static_string("Y"), CFNumber::from(20)),
+        (CFString::from_static_string("Width"), CFNumber::from(300)),
+        (CFString::from_static_string("Height"), CFNumber::from(200)),
+    ]);
+    let dict = unsafe {
+        let key = |key| CFString::wrap_under_get_rule(key);
+        CFDictionary::from_CFType_pairs(&[
+            (key(kCGWindowNumber), CFNumber::from(42).as_CFType()),
+            (key(kCGWindowOwnerPID), CFNumber::from(123).as_CFType()),
+            (key(kCGWindowOwnerName), CFString::from_static_string("Finder").as_CFType()),
+            (key(kCGWindowLayer), CFNumber::from(0).as_CFType()),
+            (key(kCGWindowBounds), bounds.as_CFType()),
+            (key(kCGWindowAlpha), CFNumber::from(0.5).as_CFType()),
+            (key(kCGWindowIsOnscreen), CFBoolean::true_value().as_CFType()),
+        ])
+    };
+    let info = CGWindowInfo::from_dictionary(&dict).unwrap();
+    assert_eq!(info.id, 42);
+    assert_eq!(info.owner_pid, 123);
+    assert_eq!(info.owner_name, Some("Finder".to_owned()));
+    assert_eq!(info.name, None);
+    assert_eq!(info.bounds.size.width, 300.);
+    assert_eq!(info.alpha, 0.5);
+    assert_eq!(info.memory_usage, 0);
+    assert!(info.is_onscreen);
+}
+
+#[test]
+fn window_list_option_from_bits_test() {
+    let option = CGWindowListOption::from(1 | 1 << 4);
+    assert_eq!(option, kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements);
+    let unknown = CGWindowImageOption::from(1 << 31 | 1 << 3);
+    assert_eq!(unknown.bits(), 1 << 31 | 1 << 3);
+    assert!(unknown.contains(kCGWindowImageBestResolution));
+    assert_eq!(CGWindowImageOption::from(1 << 3).bits(), 8);
+}
+
+#[test]
+fn window_info_iter_test() {
+    let count = CGDisplay::window_info_iter(kCGWindowListOptionOnScreenOnly, None).count();
+    assert_eq!(count, CGDisplay::window_infos(kCGWindowListOptionOnScreenOnly, None).len());
+}
+
+#[test]
+fn image_with_cursor_test() {
+    let display = CGDisplay::main();
+    let plain = display.image().unwrap();
+    let image = display.image_with_cursor().unwrap();
+    assert_eq!(image.width(), plain.width());
+    assert_eq!(image.height(), plain.height());
+}
+
+#[test]
+fn image_for_rect_clamps_to_display_test() {
+    let display = CGDisplay::main();
+    let size = display.bounds().size;
+    let oversized = CGRect::new(&CGPoint::new(-10., -10.),
+                                &CGSize::new(size.width * 2., size.height * 2.));
+    let clamped = display.clamped_capture_rect(oversized);
+    assert_eq!(clamped.origin.x, 0.);
+    assert_eq!(clamped.origin.y, 0.);
+    assert_eq!(clamped.size.width, size.width);
+    assert_eq!(clamped.size.height, size.height);
+
+    let full = display.image().unwrap();
+    let image = display.image_for_rect(oversized).unwrap();
+    assert_eq!(image.width(), full.width());
+    assert_eq!(image.height(), full.height());
 }
 
 #[link(name = "CoreGraphics", kind = "framework")]
@@ -428,34 +2127,162 @@ extern "C" {
         max_displays: libc::uint32_t,
         active_displays: *mut CGDirectDisplayID,
         display_count: *mut libc::uint32_t,
-    ) -> CGError;
+    ) -> CGErrorCode;
+    pub fn CGGetOnlineDisplayList(
+        max_displays: libc::uint32_t,
+        online_displays: *mut CGDirectDisplayID,
+        display_count: *mut libc::uint32_t,
+    ) -> CGErrorCode;
+    pub fn CGGetDisplaysWithPoint(
+        point: CGPoint,
+        max_displays: libc::uint32_t,
+        displays: *mut CGDirectDisplayID,
+        matching_display_count: *mut libc::uint32_t,
+    ) -> CGErrorCode;
     pub fn CGGetDisplaysWithRect(
         rect: CGRect,
         max_displays: libc::uint32_t,
         displays: *mut CGDirectDisplayID,
         matching_display_count: *mut libc::uint32_t,
-    ) -> CGError;
+    ) -> CGErrorCode;
     pub fn CGDisplayModelNumber(display: CGDirectDisplayID) -> libc::uint32_t;
     pub fn CGDisplayPixelsHigh(display: CGDirectDisplayID) -> libc::size_t;
     pub fn CGDisplayPixelsWide(display: CGDirectDisplayID) -> libc::size_t;
+    pub fn CGDisplayBeamPosition(display: CGDirectDisplayID) -> libc::uint32_t;
     pub fn CGDisplayBounds(display: CGDirectDisplayID) -> CGRect;
     pub fn CGDisplayCreateImage(display: CGDirectDisplayID) -> ::sys::CGImageRef;
+    pub fn CGDisplayCreateImageForRect(display: CGDirectDisplayID, rect: CGRect) -> ::sys::CGImageRef;
+
+    pub fn CGDisplayCopyColorSpace(display: CGDirectDisplayID) -> ::sys::CGColorSpaceRef;
 
     pub fn CGDisplayCopyDisplayMode(display: CGDirectDisplayID) -> ::sys::CGDisplayModeRef;
+    pub fn CGDisplayCopyAllDisplayModes(
+        display: CGDirectDisplayID,
+        options: CFDictionaryRef,
+    ) -> CFArrayRef;
+    pub fn CGDisplaySetDisplayMode(
+        display: CGDirectDisplayID,
+        mode: ::sys::CGDisplayModeRef,
+        options: CFDictionaryRef,
+    ) -> CGErrorCode;
+    pub static kCGDisplayShowDuplicateLowResolutionModes: CFStringRef;
     pub fn CGDisplayModeGetHeight(mode: ::sys::CGDisplayModeRef) -> libc::size_t;
     pub fn CGDisplayModeGetWidth(mode: ::sys::CGDisplayModeRef) -> libc::size_t;
     pub fn CGDisplayModeGetPixelHeight(mode: ::sys::CGDisplayModeRef) -> libc::size_t;
     pub fn CGDisplayModeGetPixelWidth(mode: ::sys::CGDisplayModeRef) -> libc::size_t;
     pub fn CGDisplayModeGetRefreshRate(mode: ::sys::CGDisplayModeRef) -> libc::c_double;
+    pub fn CGDisplayModeCopyPixelEncoding(mode: ::sys::CGDisplayModeRef) -> CFStringRef;
+    pub fn CGDisplayModeGetIOFlags(mode: ::sys::CGDisplayModeRef) -> libc::uint32_t;
+    pub fn CGDisplayModeGetIODisplayModeID(mode: ::sys::CGDisplayModeRef) -> libc::int32_t;
+    pub fn CGDisplayModeIsUsableForDesktopGUI(mode: ::sys::CGDisplayModeRef) -> boolean_t;
+
+    // display configuration
+    pub fn CGBeginDisplayConfiguration(config: *mut CGDisplayConfigRef) -> CGErrorCode;
+    pub fn CGCancelDisplayConfiguration(config: CGDisplayConfigRef) -> CGErrorCode;
+    pub fn CGCompleteDisplayConfiguration(
+        config: CGDisplayConfigRef,
+        option: CGConfigureOption,
+    ) -> CGErrorCode;
+    pub fn CGConfigureDisplayOrigin(
+        config: CGDisplayConfigRef,
+        display: CGDirectDisplayID,
+        x: i32,
+        y: i32,
+    ) -> CGErrorCode;
+    pub fn CGConfigureDisplayWithDisplayMode(
+        config: CGDisplayConfigRef,
+        display: CGDirectDisplayID,
+        mode: ::sys::CGDisplayModeRef,
+        options: CFDictionaryRef,
+    ) -> CGErrorCode;
+
+    // display fades
+    pub fn CGAcquireDisplayFadeReservation(
+        seconds: CGDisplayReservationInterval,
+        token: *mut CGDisplayFadeReservationToken,
+    ) -> CGErrorCode;
+    pub fn CGDisplayFade(
+        token: CGDisplayFadeReservationToken,
+        duration: CGDisplayFadeInterval,
+        startBlend: CGDisplayBlendFraction,
+        endBlend: CGDisplayBlendFraction,
+        redBlend: libc::c_float,
+        greenBlend: libc::c_float,
+        blueBlend: libc::c_float,
+        synchronous: boolean_t,
+    ) -> CGErrorCode;
+    pub fn CGReleaseDisplayFadeReservation(token: CGDisplayFadeReservationToken) -> CGErrorCode;
 
     // mouse stuff
-    pub fn CGDisplayHideCursor(display: CGDirectDisplayID) -> CGError;
-    pub fn CGDisplayShowCursor(display: CGDirectDisplayID) -> CGError;
-    pub fn CGDisplayMoveCursorToPoint(display: CGDirectDisplayID, point: CGPoint) -> CGError;
-    pub fn CGWarpMouseCursorPosition(point: CGPoint) -> CGError;
-    pub fn CGAssociateMouseAndMouseCursorPosition(connected: boolean_t) -> CGError;
+    pub fn CGDisplayRegisterReconfigurationCallback(
+        callback: CGDisplayReconfigurationCallBack,
+        user_info: *mut libc::c_void,
+    ) -> CGErrorCode;
+    pub fn CGDisplayRemoveReconfigurationCallback(
+        callback: CGDisplayReconfigurationCallBack,
+        user_info: *mut libc::c_void,
+    ) -> CGErrorCode;
+
+    // gamma
+    pub fn CGSetDisplayTransferByFormula(
+        display: CGDirectDisplayID,
+        redMin: CGGammaValue,
+        redMax: CGGammaValue,
+        redGamma: CGGammaValue,
+        greenMin: CGGammaValue,
+        greenMax: CGGammaValue,
+        greenGamma: CGGammaValue,
+        blueMin: CGGammaValue,
+        blueMax: CGGammaValue,
+        blueGamma: CGGammaValue,
+    ) -> CGErrorCode;
+    pub fn CGSetDisplayTransferByTable(
+        display: CGDirectDisplayID,
+        tableSize: libc::uint32_t,
+        redTable: *const CGGammaValue,
+        greenTable: *const CGGammaValue,
+        blueTable: *const CGGammaValue,
+    ) -> CGErrorCode;
+    pub fn CGGetDisplayTransferByTable(
+        display: CGDirectDisplayID,
+        capacity: libc::uint32_t,
+        redTable: *mut CGGammaValue,
+        greenTable: *mut CGGammaValue,
+        blueTable: *mut CGGammaValue,
+        sampleCount: *mut libc::uint32_t,
+    ) -> CGErrorCode;
+    pub fn CGDisplayGammaTableCapacity(display: CGDirectDisplayID) -> libc::uint32_t;
+    pub fn CGDisplayRestoreColorSyncSettings();
+
+    pub fn CGDisplayCapture(display: CGDirectDisplayID) -> CGErrorCode;
+    pub fn CGDisplayCaptureWithOptions(
+        display: CGDirectDisplayID,
+        options: CGCaptureOptions,
+    ) -> CGErrorCode;
+    pub fn CGDisplayRelease(display: CGDirectDisplayID) -> CGErrorCode;
+    pub fn CGDisplayGetDrawingContext(display: CGDirectDisplayID) -> ::sys::CGContextRef;
+    pub fn CGCaptureAllDisplays() -> CGErrorCode;
+    pub fn CGReleaseAllDisplays() -> CGErrorCode;
+    pub fn CGShieldingWindowLevel() -> CGWindowLevel;
+    pub fn CGShieldingWindowID(display: CGDirectDisplayID) -> CGWindowID;
+
+    pub fn CGDisplayHideCursor(display: CGDirectDisplayID) -> CGErrorCode;
+    pub fn CGDisplayShowCursor(display: CGDirectDisplayID) -> CGErrorCode;
+    pub fn CGDisplayMoveCursorToPoint(display: CGDirectDisplayID, point: CGPoint) -> CGErrorCode;
+    pub fn CGWarpMouseCursorPosition(point: CGPoint) -> CGErrorCode;
+    pub fn CGAssociateMouseAndMouseCursorPosition(connected: boolean_t) -> CGErrorCode;
 
     // Window Services Reference
+    pub static kCGWindowNumber: CFStringRef;
+    pub static kCGWindowOwnerPID: CFStringRef;
+    pub static kCGWindowOwnerName: CFStringRef;
+    pub static kCGWindowName: CFStringRef;
+    pub static kCGWindowLayer: CFStringRef;
+    pub static kCGWindowBounds: CFStringRef;
+    pub static kCGWindowIsOnscreen: CFStringRef;
+    pub static kCGWindowAlpha: CFStringRef;
+    pub static kCGWindowMemoryUsage: CFStringRef;
+
     pub fn CGWindowListCopyWindowInfo(
         option: CGWindowListOption,
         relativeToWindow: CGWindowID,
@@ -472,3 +2299,24 @@ extern "C" {
         imageOptions: CGWindowImageOption,
     ) -> ::sys::CGImageRef;
 }
+
+enum CVDisplayLink {}
+type CVDisplayLinkRef = *mut CVDisplayLink;
+
+#[repr(C)]
+#[allow(non_snake_case)]
+struct CVTime {
+    timeValue: i64,
+    timeScale: i32,
+    flags: i32,
+}
+
+const kCVTimeIsIndefinite: i32 = 1 << 0;
+
+#[link(name = "CoreVideo", kind = "framework")]
+extern "C" {
+    fn CVDisplayLinkCreateWithCGDisplay(display: CGDirectDisplayID,
+                                        link: *mut CVDisplayLinkRef) -> i32;
+    fn CVDisplayLinkGetNominalOutputVideoRefreshPeriod(link: CVDisplayLinkRef) -> CVTime;
+    fn CVDisplayLinkRelease(link: CVDisplayLinkRef);
+}
@@ -0,0 +1,302 @@
+// Copyright 2018 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Streams of display updates, for capturing a display continuously.
+
+#![allow(non_upper_case_globals)]
+
+use base::{CGError, CGErrorCode};
+use block::{Block, ConcreteBlock};
+use core_foundation::base::{CFRelease, CFRetain, CFType, CFTypeID, TCFType};
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::string::{CFString, CFStringRef};
+use display::{CGDirectDisplayID, CGDisplay};
+use foreign_types::{ForeignType, ForeignTypeRef};
+use geometry::CGRect;
+use libc::{c_char, c_void, size_t};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+pub enum __IOSurface {}
+/// A reference to the `IOSurface` holding a frame, e.g. for use with the
+/// `io-surface` crate.
+pub type IOSurfaceRef = *mut __IOSurface;
+
+pub type CGDisplayStreamFrameStatus = i32;
+
+/// A new frame was generated.
+pub const kCGDisplayStreamFrameStatusFrameComplete: CGDisplayStreamFrameStatus = 0;
+/// The display hasn't changed, so no new frame was generated.
+pub const kCGDisplayStreamFrameStatusFrameIdle: CGDisplayStreamFrameStatus = 1;
+/// The display has gone blank, e.g. because it is asleep.
+pub const kCGDisplayStreamFrameStatusFrameBlank: CGDisplayStreamFrameStatus = 2;
+/// The stream was stopped; the handler won't be called again.
+pub const kCGDisplayStreamFrameStatusStopped: CGDisplayStreamFrameStatus = 3;
+
+pub type CGDisplayStreamUpdateRectType = i32;
+
+/// The rects that were redrawn, including those that merely moved.
+pub const kCGDisplayStreamUpdateRefreshedRects: CGDisplayStreamUpdateRectType = 0;
+/// The destination rects of content that moved, see `moved_rects_delta`.
+pub const kCGDisplayStreamUpdateMovedRects: CGDisplayStreamUpdateRectType = 1;
+/// The rects whose content changed.
+pub const kCGDisplayStreamUpdateDirtyRects: CGDisplayStreamUpdateRectType = 2;
+/// Like `kCGDisplayStreamUpdateDirtyRects`, coalesced into fewer rects.
+pub const kCGDisplayStreamUpdateReducedDirtyRects: CGDisplayStreamUpdateRectType = 3;
+
+/// The `'BGRA'` pixel format: 8-bit premultiplied BGRA. The stream also
+/// accepts `'l10r'`, `'420v'` and `'420f'`.
+pub const kCVPixelFormatType_32BGRA: i32 = 0x42475241;
+
+foreign_type! {
+    #[doc(hidden)]
+    type CType = ::sys::CGDisplayStream;
+    // Stops the stream, which otherwise keeps calling the handler.
+    fn drop = |p| {
+        CGDisplayStreamStop(p);
+        CFRelease(p as *mut _)
+    };
+    pub struct CGDisplayStream;
+    pub struct CGDisplayStreamRef;
+}
+
+foreign_type! {
+    #[doc(hidden)]
+    type CType = ::sys::CGDisplayStreamUpdate;
+    fn drop = |p| CFRelease(p as *mut _);
+    fn clone = |p| CFRetain(p as *const _) as *mut _;
+    pub struct CGDisplayStreamUpdate;
+    pub struct CGDisplayStreamUpdateRef;
+}
+
+impl CGDisplayStream {
+    pub fn type_id() -> CFTypeID {
+        unsafe {
+            CGDisplayStreamGetTypeID()
+        }
+    }
+
+    /// Creates a stream of the frames of `display`, scaled to
+    /// `output_width` by `output_height` pixels in `pixel_format`.
+    ///
+    /// `handler` is called on a private serial queue for each new frame with
+    /// its status, its display time in mach absolute time units, the surface
+    /// holding the frame and the update describing what changed since the
+    /// previous frame. The surface is only valid for the duration of the call
+    /// unless retained. `properties` may contain any of the
+    /// `kCGDisplayStream*` keys.
+    ///
+    /// The stream doesn't produce frames until it is started, and is stopped
+    /// when it is dropped. A panic in `handler` drops the frame. Returns `None`
+    /// if the stream can't be created, e.g. because the process isn't allowed
+    /// to record the screen.
+    pub fn new<F>(display: &CGDisplay,
+                  output_width: usize,
+                  output_height: usize,
+                  pixel_format: i32,
+                  properties: Option<&CFDictionary<CFString, CFType>>,
+                  handler: F)
+                  -> Option<CGDisplayStream>
+                  where F: Fn(CGDisplayStreamFrameStatus,
+                              u64,
+                              Option<IOSurfaceRef>,
+                              Option<&CGDisplayStreamUpdateRef>) + Send + 'static {
+        let block = ConcreteBlock::new(move |status: CGDisplayStreamFrameStatus,
+                                             display_time: u64,
+                                             surface: IOSurfaceRef,
+                                             update: ::sys::CGDisplayStreamUpdateRef| {
+            let surface = if surface.is_null() { None } else { Some(surface) };
+            let update = if update.is_null() {
+                None
+            } else {
+                Some(unsafe { CGDisplayStreamUpdateRef::from_ptr(update) })
+            };
+            // Panics mustn't unwind into the dispatch queue.
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                handler(status, display_time, surface, update)
+            }));
+        });
+        // The stream copies the block, so this reference can go once it's
+        // created.
+        let block = block.copy();
+        let properties = properties.map_or(ptr::null(), |properties| {
+            properties.as_concrete_TypeRef()
+        });
+        unsafe {
+            let queue = dispatch_queue_create(b"core-graphics.display-stream\0".as_ptr() as *const _,
+                                              ptr::null_mut());
+            let stream = CGDisplayStreamCreateWithDispatchQueue(display.id,
+                                                                output_width,
+                                                                output_height,
+                                                                pixel_format,
+                                                                properties,
+                                                                queue,
+                                                                &*block);
+            dispatch_release(queue);
+            if !stream.is_null() {
+                Some(CGDisplayStream::from_ptr(stream))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+impl CGDisplayStreamRef {
+    /// Starts delivering frames to the handler.
+    pub fn start(&self) -> Result<(), CGError> {
+        let result = unsafe { CGDisplayStreamStart(self.as_ptr()) };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+
+    /// Stops delivering frames. The handler is called one last time with
+    /// `kCGDisplayStreamFrameStatusStopped` once the stream has stopped.
+    pub fn stop(&self) -> Result<(), CGError> {
+        let result = unsafe { CGDisplayStreamStop(self.as_ptr()) };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(CGError::from_code(result))
+        }
+    }
+}
+
+impl CGDisplayStreamUpdate {
+    pub fn type_id() -> CFTypeID {
+        unsafe {
+            CGDisplayStreamUpdateGetTypeID()
+        }
+    }
+}
+
+impl CGDisplayStreamUpdateRef {
+    /// Returns the rects of the frame of the given kind, in pixels of the
+    /// frame with the origin at the top left.
+    pub fn rects(&self, rect_type: CGDisplayStreamUpdateRectType) -> Vec<CGRect> {
+        unsafe {
+            let mut count = 0;
+            let rects = CGDisplayStreamUpdateGetRects(self.as_ptr(), rect_type, &mut count);
+            if rects.is_null() {
+                return vec![];
+            }
+            slice::from_raw_parts(rects, count).to_vec()
+        }
+    }
+
+    /// Returns the rects whose content changed since the previous frame.
+    #[inline]
+    pub fn dirty_rects(&self) -> Vec<CGRect> {
+        self.rects(kCGDisplayStreamUpdateDirtyRects)
+    }
+
+    /// Returns by how much the content of the moved rects moved, in pixels.
+    pub fn moved_rects_delta(&self) -> (f64, f64) {
+        let (mut dx, mut dy) = (0., 0.);
+        unsafe {
+            CGDisplayStreamUpdateGetMovedRectsDelta(self.as_ptr(), &mut dx, &mut dy);
+        }
+        (dx, dy)
+    }
+
+    /// Returns the number of frames that were dropped since the previous
+    /// frame, e.g. because the handler took too long.
+    pub fn drop_count(&self) -> usize {
+        unsafe {
+            CGDisplayStreamUpdateGetDropCount(self.as_ptr())
+        }
+    }
+
+    /// Returns an update combining this one with the later update `other`,
+    /// e.g. to accumulate the changes of frames that are skipped.
+    pub fn merged_with(&self, other: &CGDisplayStreamUpdateRef) -> CGDisplayStreamUpdate {
+        unsafe {
+            let update = CGDisplayStreamUpdateCreateMergedUpdate(self.as_ptr(),
+                                                                 other.as_ptr());
+            assert!(!update.is_null());
+            CGDisplayStreamUpdate::from_ptr(update)
+        }
+    }
+}
+
+#[test]
+#[ignore] // needs permission to record the screen
+fn display_stream_start_stop_test() {
+    use std::sync::mpsc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    let (sender, receiver) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let stream = CGDisplayStream::new(&CGDisplay::main(), 64, 64, kCVPixelFormatType_32BGRA,
+                                      None, move |status, _, surface, _| {
+        if status == kCGDisplayStreamFrameStatusFrameComplete {
+            let _ = sender.lock().unwrap().send(surface.is_some());
+        }
+    }).unwrap();
+    stream.start().unwrap();
+    assert!(receiver.recv_timeout(Duration::from_secs(5)).unwrap());
+    stream.stop().unwrap();
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+#[allow(improper_ctypes)]
+extern {
+    pub static kCGDisplayStreamSourceRect: CFStringRef;
+    pub static kCGDisplayStreamDestinationRect: CFStringRef;
+    pub static kCGDisplayStreamPreserveAspectRatio: CFStringRef;
+    pub static kCGDisplayStreamColorSpace: CFStringRef;
+    pub static kCGDisplayStreamMinimumFrameTime: CFStringRef;
+    pub static kCGDisplayStreamShowCursor: CFStringRef;
+    pub static kCGDisplayStreamQueueDepth: CFStringRef;
+    pub static kCGDisplayStreamYCbCrMatrix: CFStringRef;
+
+    fn CGDisplayStreamGetTypeID() -> CFTypeID;
+    fn CGDisplayStreamCreateWithDispatchQueue(
+        display: CGDirectDisplayID,
+        output_width: size_t,
+        output_height: size_t,
+        pixel_format: i32,
+        properties: CFDictionaryRef,
+        queue: dispatch_queue_t,
+        handler: &Block<(CGDisplayStreamFrameStatus,
+                         u64,
+                         IOSurfaceRef,
+                         ::sys::CGDisplayStreamUpdateRef), ()>,
+    ) -> ::sys::CGDisplayStreamRef;
+    fn CGDisplayStreamStart(stream: ::sys::CGDisplayStreamRef) -> CGErrorCode;
+    fn CGDisplayStreamStop(stream: ::sys::CGDisplayStreamRef) -> CGErrorCode;
+
+    fn CGDisplayStreamUpdateGetTypeID() -> CFTypeID;
+    fn CGDisplayStreamUpdateGetRects(update: ::sys::CGDisplayStreamUpdateRef,
+                                     rect_type: CGDisplayStreamUpdateRectType,
+                                     rect_count: *mut size_t)
+                                     -> *const CGRect;
+    fn CGDisplayStreamUpdateGetMovedRectsDelta(update: ::sys::CGDisplayStreamUpdateRef,
+                                               dx: *mut f64,
+                                               dy: *mut f64);
+    fn CGDisplayStreamUpdateGetDropCount(update: ::sys::CGDisplayStreamUpdateRef) -> size_t;
+    fn CGDisplayStreamUpdateCreateMergedUpdate(first: ::sys::CGDisplayStreamUpdateRef,
+                                               second: ::sys::CGDisplayStreamUpdateRef)
+                                               -> ::sys::CGDisplayStreamUpdateRef;
+}
+
+#[allow(non_camel_case_types)]
+enum dispatch_queue_s {}
+#[allow(non_camel_case_types)]
+type dispatch_queue_t = *mut dispatch_queue_s;
+
+extern {
+    fn dispatch_queue_create(label: *const c_char, attr: *mut c_void) -> dispatch_queue_t;
+    fn dispatch_release(object: dispatch_queue_t);
+}
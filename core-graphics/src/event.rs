@@ -1,10 +1,20 @@
 #![allow(non_upper_case_globals)]
 
-use core_foundation::base::{CFRelease, CFRetain, CFTypeID};
-use geometry::CGPoint;
-use event_source::CGEventSource;
+use core_foundation::base::{CFRelease, CFRetain, CFTypeID, TCFType};
+use core_foundation::mach_port::{CFMachPort, CFMachPortRef};
+use core_foundation::runloop::{CFRunLoop, CFRunLoopRunInMode, kCFRunLoopCommonModes};
+use core_foundation::runloop::kCFRunLoopDefaultMode;
+use core_foundation::string::CFStringRef;
+use geometry::{CGPoint, CGVector};
+use event_source::{CGEventSource, CGEventSourceStateID};
 
 use libc;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
 
 use foreign_types::ForeignType;
 
@@ -92,6 +102,56 @@ impl KeyCode {
     pub const RIGHT_ARROW: CGKeyCode = 0x7C;
     pub const DOWN_ARROW: CGKeyCode = 0x7D;
     pub const UP_ARROW: CGKeyCode = 0x7E;
+
+    // Keys whose character depends on the keyboard layout, named after their
+    // position on an ANSI (US) keyboard.
+    pub const ANSI_A: CGKeyCode = 0x00;
+    pub const ANSI_S: CGKeyCode = 0x01;
+    pub const ANSI_D: CGKeyCode = 0x02;
+    pub const ANSI_F: CGKeyCode = 0x03;
+    pub const ANSI_H: CGKeyCode = 0x04;
+    pub const ANSI_G: CGKeyCode = 0x05;
+    pub const ANSI_Z: CGKeyCode = 0x06;
+    pub const ANSI_X: CGKeyCode = 0x07;
+    pub const ANSI_C: CGKeyCode = 0x08;
+    pub const ANSI_V: CGKeyCode = 0x09;
+    pub const ANSI_B: CGKeyCode = 0x0B;
+    pub const ANSI_Q: CGKeyCode = 0x0C;
+    pub const ANSI_W: CGKeyCode = 0x0D;
+    pub const ANSI_E: CGKeyCode = 0x0E;
+    pub const ANSI_R: CGKeyCode = 0x0F;
+    pub const ANSI_Y: CGKeyCode = 0x10;
+    pub const ANSI_T: CGKeyCode = 0x11;
+    pub const ANSI_1: CGKeyCode = 0x12;
+    pub const ANSI_2: CGKeyCode = 0x13;
+    pub const ANSI_3: CGKeyCode = 0x14;
+    pub const ANSI_4: CGKeyCode = 0x15;
+    pub const ANSI_6: CGKeyCode = 0x16;
+    pub const ANSI_5: CGKeyCode = 0x17;
+    pub const ANSI_EQUAL: CGKeyCode = 0x18;
+    pub const ANSI_9: CGKeyCode = 0x19;
+    pub const ANSI_7: CGKeyCode = 0x1A;
+    pub const ANSI_MINUS: CGKeyCode = 0x1B;
+    pub const ANSI_8: CGKeyCode = 0x1C;
+    pub const ANSI_0: CGKeyCode = 0x1D;
+    pub const ANSI_RIGHT_BRACKET: CGKeyCode = 0x1E;
+    pub const ANSI_O: CGKeyCode = 0x1F;
+    pub const ANSI_U: CGKeyCode = 0x20;
+    pub const ANSI_LEFT_BRACKET: CGKeyCode = 0x21;
+    pub const ANSI_I: CGKeyCode = 0x22;
+    pub const ANSI_P: CGKeyCode = 0x23;
+    pub const ANSI_L: CGKeyCode = 0x25;
+    pub const ANSI_J: CGKeyCode = 0x26;
+    pub const ANSI_QUOTE: CGKeyCode = 0x27;
+    pub const ANSI_K: CGKeyCode = 0x28;
+    pub const ANSI_SEMICOLON: CGKeyCode = 0x29;
+    pub const ANSI_BACKSLASH: CGKeyCode = 0x2A;
+    pub const ANSI_COMMA: CGKeyCode = 0x2B;
+    pub const ANSI_SLASH: CGKeyCode = 0x2C;
+    pub const ANSI_N: CGKeyCode = 0x2D;
+    pub const ANSI_M: CGKeyCode = 0x2E;
+    pub const ANSI_PERIOD: CGKeyCode = 0x2F;
+    pub const ANSI_GRAVE: CGKeyCode = 0x32;
 }
 
 /// Constants that specify the different types of input events.
@@ -130,6 +190,33 @@ pub enum CGEventType {
     TapDisabledByUserInput = 0xFFFFFFFF,
 }
 
+impl CGEventType {
+    fn from_raw(raw: libc::uint32_t) -> Option<CGEventType> {
+        Some(match raw {
+            0 => CGEventType::Null,
+            1 => CGEventType::LeftMouseDown,
+            2 => CGEventType::LeftMouseUp,
+            3 => CGEventType::RightMouseDown,
+            4 => CGEventType::RightMouseUp,
+            5 => CGEventType::MouseMoved,
+            6 => CGEventType::LeftMouseDragged,
+            7 => CGEventType::RightMouseDragged,
+            10 => CGEventType::KeyDown,
+            11 => CGEventType::KeyUp,
+            12 => CGEventType::FlagsChanged,
+            22 => CGEventType::ScrollWheel,
+            23 => CGEventType::TabletPointer,
+            24 => CGEventType::TabletProximity,
+            25 => CGEventType::OtherMouseDown,
+            26 => CGEventType::OtherMouseUp,
+            27 => CGEventType::OtherMouseDragged,
+            0xFFFFFFFE => CGEventType::TapDisabledByTimeout,
+            0xFFFFFFFF => CGEventType::TapDisabledByUserInput,
+            _ => return None,
+        })
+    }
+}
+
 /// Constants used as keys to access specialized fields in low-level events.
 ///
 /// [Ref](https://developer.apple.com/documentation/coregraphics/cgeventfield)
@@ -194,6 +281,10 @@ impl EventField {
     /// scrolling event from a Mighty Mouse scroller.
     pub const SCROLL_WHEEL_EVENT_DELTA_AXIS_2: CGEventField = 12;
 
+    /// Key to access an integer field that contains scrolling data. This field
+    /// is reserved for a third scroll wheel axis and is currently unused.
+    pub const SCROLL_WHEEL_EVENT_DELTA_AXIS_3: CGEventField = 13;
+
     /// Key to access a field that contains scrolling data. The scrolling data
     /// represents a line-based or pixel-based change in vertical position
     /// since the last scrolling event from a Mighty Mouse scroller or a
@@ -211,6 +302,10 @@ impl EventField {
     /// converted to a double value.
     pub const SCROLL_WHEEL_EVENT_FIXED_POINT_DELTA_AXIS_2: CGEventField = 94;
 
+    /// Like `SCROLL_WHEEL_EVENT_FIXED_POINT_DELTA_AXIS_2`, for the unused
+    /// third axis.
+    pub const SCROLL_WHEEL_EVENT_FIXED_POINT_DELTA_AXIS_3: CGEventField = 95;
+
     /// Key to access an integer field that contains pixel-based scrolling
     /// data. The scrolling data represents the change in vertical position
     /// since the last scrolling event from a Mighty Mouse scroller or a
@@ -222,6 +317,23 @@ impl EventField {
     /// since the last scrolling event from a Mighty Mouse scroller.
     pub const SCROLL_WHEEL_EVENT_POINT_DELTA_AXIS_2: CGEventField = 97;
 
+    /// Like `SCROLL_WHEEL_EVENT_POINT_DELTA_AXIS_2`, for the unused third
+    /// axis.
+    pub const SCROLL_WHEEL_EVENT_POINT_DELTA_AXIS_3: CGEventField = 98;
+
+    /// Key to access an integer field that contains the phase of a trackpad
+    /// scroll gesture, e.g. began, changed or ended.
+    pub const SCROLL_WHEEL_EVENT_SCROLL_PHASE: CGEventField = 99;
+
+    /// Key to access an integer field that contains the number of scroll
+    /// gestures in quick succession, used for scroll acceleration.
+    pub const SCROLL_WHEEL_EVENT_SCROLL_COUNT: CGEventField = 100;
+
+    /// Key to access an integer field that contains the momentum phase of a
+    /// scroll event, which is non-zero for events generated by inertial
+    /// scrolling after the fingers left the trackpad.
+    pub const SCROLL_WHEEL_EVENT_MOMENTUM_PHASE: CGEventField = 123;
+
     /// Key to access an integer field that indicates whether the event should
     /// be ignored by the Inkwell subsystem. If the value is non-zero, the
     /// event should be ignored.
@@ -360,13 +472,47 @@ impl EventField {
 
 // Constants that specify buttons on a one, two, or three-button mouse.
 #[repr(C)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CGMouseButton {
     Left,
     Right,
     Center,
 }
 
+impl CGMouseButton {
+    fn down_event_type(&self) -> CGEventType {
+        match *self {
+            CGMouseButton::Left => CGEventType::LeftMouseDown,
+            CGMouseButton::Right => CGEventType::RightMouseDown,
+            CGMouseButton::Center => CGEventType::OtherMouseDown,
+        }
+    }
+
+    fn up_event_type(&self) -> CGEventType {
+        match *self {
+            CGMouseButton::Left => CGEventType::LeftMouseUp,
+            CGMouseButton::Right => CGEventType::RightMouseUp,
+            CGMouseButton::Center => CGEventType::OtherMouseUp,
+        }
+    }
+
+    fn dragged_event_type(&self) -> CGEventType {
+        match *self {
+            CGMouseButton::Left => CGEventType::LeftMouseDragged,
+            CGMouseButton::Right => CGEventType::RightMouseDragged,
+            CGMouseButton::Center => CGEventType::OtherMouseDragged,
+        }
+    }
+}
+
+/// Units of the deltas of a scroll wheel event.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CGScrollEventUnit {
+    Pixel = 0,
+    Line = 1,
+}
+
 /// Possible tapping points for events.
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
@@ -376,6 +522,289 @@ pub enum CGEventTapLocation {
     AnnotatedSession,
 }
 
+/// Where a new event tap is inserted among the existing taps at the same
+/// location.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub enum CGEventTapPlacement {
+    HeadInsertEventTap,
+    TailAppendEventTap,
+}
+
+/// Whether an event tap can modify and drop events or only observe them.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub enum CGEventTapOptions {
+    Default,
+    ListenOnly,
+}
+
+pub type CGEventMask = libc::uint64_t;
+pub type CGEventTapProxy = *const libc::c_void;
+
+/// What an event tap does with an event it was given. Listen-only taps ignore it.
+pub enum CGEventTapResponse {
+    /// Passes the event on, including any changes made to it.
+    Keep,
+    /// Deletes the event, so that later taps and the application never see it.
+    Drop,
+    /// Passes the given event on in place of the original one.
+    Replace(CGEvent),
+}
+
+type CGEventTapCallBackFn =
+    Box<Fn(CGEventTapProxy, CGEventType, &CGEvent) -> CGEventTapResponse + Send>;
+
+/// An event tap, which observes (and for non-listen-only taps, can modify)
+/// the events of interest flowing through a tap location.
+///
+/// The tap only receives events while it is added to a running run loop, see
+/// `add_to_run_loop` and `run_on_thread`. It is disabled and torn down when
+/// dropped.
+///
+/// macOS disables a tap whose callback takes too long, or on user input while
+/// secure input is enabled, and then calls the callback once with
+/// `TapDisabledByTimeout` or `TapDisabledByUserInput`. Unless automatic
+/// re-enabling is turned off with `set_auto_reenable`, the tap is enabled
+/// again before the callback sees either of them.
+pub struct CGEventTap {
+    pub mach_port: CFMachPort,
+    // Boxed so that the pointer passed as the callback's user info stays put
+    // when the tap is moved.
+    context: Box<TapContext>,
+}
+
+struct TapContext {
+    callback: CGEventTapCallBackFn,
+    // The tap's mach port, for re-enabling it from the callback.
+    mach_port: AtomicPtr<libc::c_void>,
+    auto_reenable: AtomicBool,
+}
+
+// The mach port may be used from any thread and the callback is `Send`.
+unsafe impl Send for CGEventTap {}
+
+impl CGEventTap {
+    /// Creates a disabled event tap for the given event types.
+    ///
+    /// `callback` returns whether to keep, drop or replace the event it was
+    /// given; the response is ignored by listen-only taps. Fails if the
+    /// process isn't allowed to tap the requested events.
+    pub fn new<F>(tap: CGEventTapLocation,
+                  place: CGEventTapPlacement,
+                  options: CGEventTapOptions,
+                  events_of_interest: Vec<CGEventType>,
+                  callback: F)
+                  -> Result<CGEventTap, ()>
+                  where F: Fn(CGEventTapProxy, CGEventType, &CGEvent) -> CGEventTapResponse +
+                           Send + 'static {
+        // The out-of-band tap disabled types are always delivered and have no
+        // bit in the mask.
+        let event_mask = events_of_interest.iter()
+            .map(|&event_type| event_type as CGEventMask)
+            .filter(|&event_type| event_type < 64)
+            .fold(0, |mask, event_type| mask | 1 << event_type);
+        let context = Box::new(TapContext {
+            callback: Box::new(callback),
+            mach_port: AtomicPtr::new(ptr::null_mut()),
+            auto_reenable: AtomicBool::new(true),
+        });
+        unsafe {
+            let port = CGEventTapCreate(tap,
+                                        place,
+                                        options,
+                                        event_mask,
+                                        cg_event_tap_callback,
+                                        &*context as *const TapContext as *mut _);
+            if !port.is_null() {
+                context.mach_port.store(port as *mut _, Ordering::SeqCst);
+                Ok(CGEventTap {
+                    mach_port: CFMachPort::wrap_under_create_rule(port),
+                    context: context,
+                })
+            } else {
+                Err(())
+            }
+        }
+    }
+
+    pub fn enable(&self) {
+        unsafe {
+            CGEventTapEnable(self.mach_port.as_concrete_TypeRef(), true)
+        }
+    }
+
+    pub fn disable(&self) {
+        unsafe {
+            CGEventTapEnable(self.mach_port.as_concrete_TypeRef(), false)
+        }
+    }
+
+    /// Returns whether the tap is enabled.
+    pub fn is_enabled(&self) -> bool {
+        unsafe {
+            CGEventTapIsEnabled(self.mach_port.as_concrete_TypeRef())
+        }
+    }
+
+    /// Sets whether the tap is enabled again when macOS disables it, which it
+    /// is by default.
+    pub fn set_auto_reenable(&self, auto_reenable: bool) {
+        self.context.auto_reenable.store(auto_reenable, Ordering::SeqCst);
+    }
+
+    /// Adds the tap to `run_loop` in `mode`, e.g. `kCFRunLoopCommonModes`, so
+    /// that it receives events while the run loop runs. The tap still needs to
+    /// be enabled.
+    pub fn add_to_run_loop(&self, run_loop: &CFRunLoop, mode: CFStringRef) -> Result<(), ()> {
+        match self.mach_port.to_run_loop_source(0) {
+            Some(source) => {
+                run_loop.add_source(&source, mode);
+                Ok(())
+            }
+            None => Err(()),
+        }
+    }
+
+    /// Enables the tap and runs it on a new thread with its own run loop, until
+    /// `stop` is called on the returned handle (or it is dropped). Fails if the
+    /// tap can't be added to the thread's run loop.
+    pub fn run_on_thread(self) -> Result<TapHandle, ()> {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread_stopped = stopped.clone();
+        let (run_loop_tx, run_loop_rx) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            let run_loop = CFRunLoop::get_current();
+            if self.add_to_run_loop(&run_loop, unsafe { kCFRunLoopCommonModes }).is_err() {
+                let _ = run_loop_tx.send(Err(()));
+                return;
+            }
+            self.enable();
+            let _ = run_loop_tx.send(Ok(SendableRunLoop(run_loop)));
+            // `CFRunLoopStop` has no effect if it arrives before the run loop is
+            // running, so don't run it indefinitely.
+            while !thread_stopped.load(Ordering::SeqCst) {
+                unsafe {
+                    CFRunLoopRunInMode(kCFRunLoopDefaultMode, 1., 0);
+                }
+            }
+        });
+        match run_loop_rx.recv() {
+            Ok(Ok(run_loop)) => {
+                Ok(TapHandle {
+                    run_loop: run_loop.0,
+                    stopped: stopped,
+                    thread: Some(thread),
+                })
+            }
+            _ => {
+                let _ = thread.join();
+                Err(())
+            }
+        }
+    }
+}
+
+impl Drop for CGEventTap {
+    fn drop(&mut self) {
+        self.disable();
+        // Also removes the tap's run loop sources from their run loops.
+        self.mach_port.invalidate();
+    }
+}
+
+/// A running event tap thread, see `CGEventTap::run_on_thread`.
+pub struct TapHandle {
+    run_loop: CFRunLoop,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TapHandle {
+    /// Stops the tap's run loop and waits for its thread to finish.
+    pub fn stop(mut self) {
+        self.stop_and_join()
+    }
+
+    fn stop_and_join(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.run_loop.stop();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for TapHandle {
+    fn drop(&mut self) {
+        self.stop_and_join()
+    }
+}
+
+// Run loops can be stopped from any thread.
+struct SendableRunLoop(CFRunLoop);
+unsafe impl Send for SendableRunLoop {}
+
+unsafe extern "C" fn cg_event_tap_callback(proxy: CGEventTapProxy,
+                                           event_type: libc::uint32_t,
+                                           event_ref: ::sys::CGEventRef,
+                                           user_info: *mut libc::c_void)
+                                           -> ::sys::CGEventRef {
+    let context = &*(user_info as *const TapContext);
+    let event_type = match CGEventType::from_raw(event_type) {
+        Some(event_type) => event_type,
+        None => return event_ref,
+    };
+    match event_type {
+        CGEventType::TapDisabledByTimeout | CGEventType::TapDisabledByUserInput => {
+            let port = context.mach_port.load(Ordering::SeqCst);
+            if context.auto_reenable.load(Ordering::SeqCst) && !port.is_null() {
+                CGEventTapEnable(port as CFMachPortRef, true);
+            }
+            // The tap disabled notifications may come without an event.
+            if event_ref.is_null() {
+                let null_event = CGEventCreate(ptr::null_mut());
+                if !null_event.is_null() {
+                    let null_event = CGEvent::from_ptr(null_event);
+                    // Panics mustn't unwind into the event system.
+                    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                        (context.callback)(proxy, event_type, &null_event)
+                    }));
+                }
+                return event_ref;
+            }
+        }
+        _ => {}
+    }
+    if event_ref.is_null() {
+        return event_ref;
+    }
+    let event = CGEvent::from_ptr(CFRetain(event_ref as *const _) as *mut _);
+    match panic::catch_unwind(AssertUnwindSafe(|| (context.callback)(proxy, event_type, &event))) {
+        Ok(response) => tap_response_to_ref(response, event_ref),
+        // Passes the event through if the callback panicked.
+        Err(_) => event_ref,
+    }
+}
+
+/// Returns the event a tap callback hands back to the event system for `response` to the event
+/// `event_ref`.
+fn tap_response_to_ref(response: CGEventTapResponse, event_ref: ::sys::CGEventRef)
+                       -> ::sys::CGEventRef {
+    match response {
+        CGEventTapResponse::Keep => event_ref,
+        CGEventTapResponse::Drop => ptr::null_mut(),
+        // The event system owns the original event, so a reference to it must not be leaked.
+        CGEventTapResponse::Replace(ref new_event) if new_event.as_ptr() == event_ref => event_ref,
+        // The event system releases a new event along with the original one.
+        CGEventTapResponse::Replace(new_event) => {
+            let new_ref = new_event.as_ptr();
+            ::std::mem::forget(new_event);
+            new_ref
+        }
+    }
+}
+
 foreign_type! {
     #[doc(hidden)]
     type CType = ::sys::CGEvent;
@@ -403,6 +832,8 @@ impl CGEvent {
         }
     }
 
+    /// Creates a key down or key up event for the key `keycode`, which can be
+    /// injected with `post`.
     pub fn new_keyboard_event(
         source: CGEventSource,
         keycode: CGKeyCode,
@@ -435,6 +866,75 @@ impl CGEvent {
         }
     }
 
+    /// Creates a scroll wheel event scrolling `wheel_count` axes, of which
+    /// the first is vertical and the second horizontal, by the given deltas.
+    /// Positive deltas scroll up and left.
+    pub fn new_scroll_event(
+        source: CGEventSource,
+        units: CGScrollEventUnit,
+        wheel_count: u32,
+        wheel1: i32,
+        wheel2: i32,
+        wheel3: i32,
+    ) -> Result<CGEvent, ()> {
+        unsafe {
+            let event_ref = CGEventCreateScrollWheelEvent2(source.as_ptr(), units, wheel_count,
+                                                           wheel1, wheel2, wheel3);
+            if !event_ref.is_null() {
+                Ok(Self::from_ptr(event_ref))
+            } else {
+                Err(())
+            }
+        }
+    }
+
+    /// Clicks `button` at `point`, in the global display coordinate space, by
+    /// posting a mouse down and a mouse up event.
+    pub fn click_at(point: CGPoint, button: CGMouseButton) -> Result<(), ()> {
+        let source = try!(CGEventSource::new(CGEventSourceStateID::HIDSystemState));
+        for &event_type in &[button.down_event_type(), button.up_event_type()] {
+            try!(CGEvent::new_mouse_event(source.clone(), event_type, point, button))
+                .post(CGEventTapLocation::HID);
+        }
+        Ok(())
+    }
+
+    /// Drags with `button` held down from `from` to `to`, in the global
+    /// display coordinate space, moving through intermediate points so that
+    /// apps tracking the drag see it.
+    pub fn drag(from: CGPoint, to: CGPoint, button: CGMouseButton) -> Result<(), ()> {
+        const STEPS: u32 = 10;
+
+        let source = try!(CGEventSource::new(CGEventSourceStateID::HIDSystemState));
+        try!(CGEvent::new_mouse_event(source.clone(), button.down_event_type(), from, button))
+            .post(CGEventTapLocation::HID);
+        for step in 1..STEPS + 1 {
+            let t = step as f64 / STEPS as f64;
+            let point = CGPoint::new(from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t);
+            let event_type = button.dragged_event_type();
+            try!(CGEvent::new_mouse_event(source.clone(), event_type, point, button))
+                .post(CGEventTapLocation::HID);
+        }
+        try!(CGEvent::new_mouse_event(source, button.up_event_type(), to, button))
+            .post(CGEventTapLocation::HID);
+        Ok(())
+    }
+
+    /// Returns a copy of this event, which can be modified and posted without
+    /// affecting the original.
+    pub fn copy(&self) -> Result<CGEvent, ()> {
+        unsafe {
+            let event_ref = CGEventCreateCopy(self.as_ptr());
+            if !event_ref.is_null() {
+                Ok(Self::from_ptr(event_ref))
+            } else {
+                Err(())
+            }
+        }
+    }
+
+    /// Posts the event at `tap_location`, from where it passes through the
+    /// event taps there and further down the event system.
     pub fn post(&self, tap_location: CGEventTapLocation) {
         unsafe {
             CGEventPost(tap_location, self.as_ptr());
@@ -454,12 +954,15 @@ impl CGEvent {
         }
     }
 
+    /// Sets the modifier flags of the event, e.g. `CGEventFlagCommand` on a
+    /// key down event for `KeyCode::ANSI_C` to synthesize Cmd+C.
     pub fn set_flags(&self, flags: CGEventFlags) {
         unsafe {
             CGEventSetFlags(self.as_ptr(), flags);
         }
     }
 
+    /// Returns the modifier flags of the event.
     pub fn get_flags(&self) -> CGEventFlags {
         unsafe {
             CGEventGetFlags(self.as_ptr())
@@ -485,11 +988,71 @@ impl CGEvent {
         }
     }
 
+    /// Same as `set_unicode_string`.
     pub fn set_string(&self, string: &str) {
+        self.set_unicode_string(string)
+    }
+
+    /// Sets the text a keyboard event types instead of the character of its
+    /// key code, e.g. to type text that isn't on the current keyboard layout.
+    ///
+    /// Only the first 20 UTF-16 code units of `string` are kept; longer text
+    /// has to be split across several events.
+    pub fn set_unicode_string(&self, string: &str) {
         let buf: Vec<u16> = string.encode_utf16().collect();
         self.set_string_from_utf16_unchecked(&buf);
     }
 
+    /// Returns the text a keyboard event types. Invalid UTF-16 is replaced
+    /// with U+FFFD.
+    pub fn unicode_string(&self) -> String {
+        let mut len = 0;
+        unsafe {
+            CGEventKeyboardGetUnicodeString(self.as_ptr(), 0, &mut len, ptr::null_mut());
+        }
+        let mut buf = vec![0u16; len as usize];
+        unsafe {
+            CGEventKeyboardGetUnicodeString(self.as_ptr(), len, &mut len, buf.as_mut_ptr());
+        }
+        buf.truncate(len as usize);
+        String::from_utf16_lossy(&buf)
+    }
+
+    /// Returns the button of a mouse event. Buttons other than the left and
+    /// right ones are reported as `Center`.
+    pub fn mouse_button(&self) -> CGMouseButton {
+        match self.get_integer_value_field(EventField::MOUSE_EVENT_BUTTON_NUMBER) {
+            0 => CGMouseButton::Left,
+            1 => CGMouseButton::Right,
+            _ => CGMouseButton::Center,
+        }
+    }
+
+    /// Returns the click count of a mouse event, e.g. 2 for the second click
+    /// of a double click.
+    pub fn click_count(&self) -> i64 {
+        self.get_integer_value_field(EventField::MOUSE_EVENT_CLICK_STATE)
+    }
+
+    /// Returns how far the mouse moved since the previous mouse event, which
+    /// is reported even when the cursor is pinned to the edge of the screen.
+    pub fn mouse_delta(&self) -> CGVector {
+        CGVector::new(self.get_double_value_field(EventField::MOUSE_EVENT_DELTA_X),
+                      self.get_double_value_field(EventField::MOUSE_EVENT_DELTA_Y))
+    }
+
+    /// Returns the pressure of a mouse or tablet event, between 0 (no
+    /// pressure) and 1 (full pressure).
+    pub fn pressure(&self) -> f64 {
+        self.get_double_value_field(EventField::MOUSE_EVENT_PRESSURE)
+    }
+
+    /// Returns the process ID of the process that posted the event, or 0 if
+    /// the event came from hardware.
+    pub fn source_pid(&self) -> libc::pid_t {
+        self.get_integer_value_field(EventField::EVENT_SOURCE_UNIX_PROCESS_ID) as libc::pid_t
+    }
+
     pub fn get_integer_value_field(&self, field: CGEventField) -> i64 {
         unsafe { CGEventGetIntegerValueField(self.as_ptr(), field) }
     }
@@ -507,8 +1070,196 @@ impl CGEvent {
     }
 }
 
+#[test]
+fn copy_test() {
+    use event_source::CGEventSourceStateID;
+
+    let source = CGEventSource::new(CGEventSourceStateID::Private).unwrap();
+    let event = CGEvent::new_keyboard_event(source, KeyCode::TAB, true).unwrap();
+    let copy = event.copy().unwrap();
+    assert_eq!(copy.get_integer_value_field(EventField::KEYBOARD_EVENT_KEYCODE),
+               KeyCode::TAB as i64);
+}
+
+#[test]
+fn keyboard_event_test() {
+    use event_source::CGEventSourceStateID;
+
+    let source = CGEventSource::new(CGEventSourceStateID::Private).unwrap();
+    let event = CGEvent::new_keyboard_event(source, KeyCode::ANSI_A, false).unwrap();
+    assert_eq!(event.get_type() as u32, CGEventType::KeyUp as u32);
+    assert_eq!(event.get_integer_value_field(EventField::KEYBOARD_EVENT_KEYCODE),
+               KeyCode::ANSI_A as i64);
+}
+
+#[test]
+fn unicode_string_test() {
+    let source = CGEventSource::new(CGEventSourceStateID::Private).unwrap();
+    let event = CGEvent::new_keyboard_event(source, 0, true).unwrap();
+    event.set_unicode_string("héllo 👋");
+    assert_eq!(event.unicode_string(), "héllo 👋");
+    event.set_string("a");
+    assert_eq!(event.unicode_string(), "a");
+}
+
+#[test]
+fn value_field_test() {
+    let source = CGEventSource::new(CGEventSourceStateID::Private).unwrap();
+    let event = CGEvent::new_mouse_event(source, CGEventType::LeftMouseDown,
+                                         CGPoint::new(0., 0.), CGMouseButton::Left).unwrap();
+    event.set_integer_value_field(EventField::MOUSE_EVENT_CLICK_STATE, 3);
+    assert_eq!(event.click_count(), 3);
+    event.set_double_value_field(EventField::MOUSE_EVENT_PRESSURE, 0.5);
+    assert_eq!(event.pressure(), 0.5);
+}
+
+#[test]
+fn flags_test() {
+    let source = CGEventSource::new(CGEventSourceStateID::Private).unwrap();
+    let event = CGEvent::new_keyboard_event(source, KeyCode::ANSI_C, true).unwrap();
+    event.set_flags(CGEventFlags::CGEventFlagCommand);
+    assert_eq!(event.get_flags(), CGEventFlags::CGEventFlagCommand);
+    event.set_flags(event.get_flags() | CGEventFlags::CGEventFlagShift);
+    assert!(event.get_flags().contains(CGEventFlags::CGEventFlagCommand |
+                                       CGEventFlags::CGEventFlagShift));
+}
+
+#[test]
+fn scroll_event_test() {
+    let source = CGEventSource::new(CGEventSourceStateID::Private).unwrap();
+    let event = CGEvent::new_scroll_event(source, CGScrollEventUnit::Line, 2, 3, -1, 0).unwrap();
+    assert_eq!(event.get_type() as u32, CGEventType::ScrollWheel as u32);
+    assert_eq!(event.get_integer_value_field(EventField::SCROLL_WHEEL_EVENT_DELTA_AXIS_1), 3);
+    assert_eq!(event.get_integer_value_field(EventField::SCROLL_WHEEL_EVENT_DELTA_AXIS_2), -1);
+}
+
+#[test]
+fn click_count_test() {
+    use event_source::CGEventSourceStateID;
+
+    let source = CGEventSource::new(CGEventSourceStateID::Private).unwrap();
+    let event = CGEvent::new_mouse_event(source, CGEventType::LeftMouseDown,
+                                         CGPoint::new(10., 10.), CGMouseButton::Left).unwrap();
+    event.set_integer_value_field(EventField::MOUSE_EVENT_CLICK_STATE, 2);
+    assert_eq!(event.click_count(), 2);
+    assert_eq!(event.mouse_button(), CGMouseButton::Left);
+}
+
+#[test]
+#[ignore] // needs permission to monitor input
+fn event_tap_drop_test() {
+    let tap = CGEventTap::new(CGEventTapLocation::Session,
+                              CGEventTapPlacement::HeadInsertEventTap,
+                              CGEventTapOptions::ListenOnly,
+                              vec![CGEventType::KeyDown],
+                              |_, _, event| {
+                                  let _ = event.get_integer_value_field(
+                                      EventField::KEYBOARD_EVENT_KEYCODE);
+                                  CGEventTapResponse::Keep
+                              }).unwrap();
+    tap.add_to_run_loop(&CFRunLoop::get_current(), unsafe { kCFRunLoopCommonModes }).unwrap();
+    tap.enable();
+    let port = tap.mach_port.clone();
+    drop(tap);
+    assert!(!port.valid());
+}
+
+#[test]
+fn event_tap_callback_test() {
+    let context = TapContext {
+        callback: Box::new(|_, _, _| panic!("callback panicked")),
+        mach_port: AtomicPtr::new(ptr::null_mut()),
+        auto_reenable: AtomicBool::new(false),
+    };
+    let context = &context as *const TapContext as *mut _;
+    let source = CGEventSource::new(CGEventSourceStateID::Private).unwrap();
+    let event = CGEvent::new_keyboard_event(source, KeyCode::SPACE, true).unwrap();
+    unsafe {
+        // Panics are caught and the event is passed through.
+        assert_eq!(cg_event_tap_callback(ptr::null(), CGEventType::KeyDown as u32,
+                                         event.as_ptr(), context),
+                   event.as_ptr());
+        assert!(cg_event_tap_callback(ptr::null(), CGEventType::KeyDown as u32,
+                                      ptr::null_mut(), context).is_null());
+    }
+}
+
+#[test]
+fn tap_response_to_ref_test() {
+    use core_foundation::base::CFGetRetainCount;
+
+    let source = CGEventSource::new(CGEventSourceStateID::Private).unwrap();
+    let event = CGEvent::new_keyboard_event(source.clone(), KeyCode::SPACE, true).unwrap();
+    let new_event = CGEvent::new_keyboard_event(source, KeyCode::SPACE, false).unwrap();
+    let (event_ref, new_ref) = (event.as_ptr(), new_event.as_ptr());
+    unsafe {
+        let retain_count = CFGetRetainCount(event_ref as *const _);
+        assert_eq!(tap_response_to_ref(CGEventTapResponse::Keep, event_ref), event_ref);
+        assert!(tap_response_to_ref(CGEventTapResponse::Drop, event_ref).is_null());
+        assert_eq!(tap_response_to_ref(CGEventTapResponse::Replace(event.clone()), event_ref),
+                   event_ref);
+        assert_eq!(CFGetRetainCount(event_ref as *const _), retain_count);
+
+        // The new event is handed over to the event system, which releases it.
+        assert_eq!(tap_response_to_ref(CGEventTapResponse::Replace(new_event), event_ref),
+                   new_ref);
+        CFRelease(new_ref as *mut _);
+    }
+}
+
+#[test]
+#[ignore] // needs permission to monitor input
+fn event_tap_reenable_test() {
+    let (sender, receiver) = mpsc::channel();
+    let sender = ::std::sync::Mutex::new(sender);
+    let tap = CGEventTap::new(CGEventTapLocation::Session,
+                              CGEventTapPlacement::HeadInsertEventTap,
+                              CGEventTapOptions::ListenOnly,
+                              vec![CGEventType::KeyDown],
+                              move |_, event_type, _| {
+                                  let _ = sender.lock().unwrap().send(event_type as u32);
+                                  CGEventTapResponse::Keep
+                              }).unwrap();
+    // Simulate macOS disabling the tap.
+    tap.disable();
+    unsafe {
+        cg_event_tap_callback(ptr::null(),
+                              CGEventType::TapDisabledByTimeout as u32,
+                              ptr::null_mut(),
+                              &*tap.context as *const TapContext as *mut _);
+    }
+    assert_eq!(receiver.try_recv(), Ok(CGEventType::TapDisabledByTimeout as u32));
+    assert!(tap.is_enabled());
+}
+
+#[test]
+#[ignore] // needs permission to monitor input
+fn run_on_thread_test() {
+    let tap = CGEventTap::new(CGEventTapLocation::Session,
+                              CGEventTapPlacement::HeadInsertEventTap,
+                              CGEventTapOptions::ListenOnly,
+                              vec![CGEventType::MouseMoved],
+                              |_, _, _| CGEventTapResponse::Keep).unwrap();
+    let handle = tap.run_on_thread().unwrap();
+    handle.stop();
+}
+
 #[link(name = "CoreGraphics", kind = "framework")]
 extern {
+    fn CGEventTapCreate(tap: CGEventTapLocation,
+                        place: CGEventTapPlacement,
+                        options: CGEventTapOptions,
+                        events_of_interest: CGEventMask,
+                        callback: unsafe extern "C" fn(CGEventTapProxy,
+                                                       libc::uint32_t,
+                                                       ::sys::CGEventRef,
+                                                       *mut libc::c_void)
+                                                       -> ::sys::CGEventRef,
+                        user_info: *mut libc::c_void)
+                        -> CFMachPortRef;
+    fn CGEventTapEnable(tap: CFMachPortRef, enable: bool);
+    fn CGEventTapIsEnabled(tap: CFMachPortRef) -> bool;
+
     /// Return the type identifier for the opaque type `CGEventRef'.
     fn CGEventGetTypeID() -> CFTypeID;
 
@@ -516,6 +1267,9 @@ extern {
     /// the default source is used.
     fn CGEventCreate(source: ::sys::CGEventSourceRef) -> ::sys::CGEventRef;
 
+    /// Return a copy of `event'.
+    fn CGEventCreateCopy(event: ::sys::CGEventRef) -> ::sys::CGEventRef;
+
     /// Return a new keyboard event.
     ///
     /// The event source may be taken from another event, or may be NULL. Based
@@ -545,6 +1299,15 @@ extern {
     fn CGEventCreateMouseEvent(source: ::sys::CGEventSourceRef, mouseType: CGEventType,
         mouseCursorPosition: CGPoint, mouseButton: CGMouseButton) -> ::sys::CGEventRef;
 
+    /// Return a new Quartz scroll wheel event.
+    fn CGEventCreateScrollWheelEvent2(source: ::sys::CGEventSourceRef,
+                                      units: CGScrollEventUnit,
+                                      wheelCount: libc::uint32_t,
+                                      wheel1: i32,
+                                      wheel2: i32,
+                                      wheel3: i32)
+                                      -> ::sys::CGEventRef;
+
     /// Post an event into the event stream at a specified location.
     ///
     /// This function posts the specified event immediately before any event taps
@@ -584,6 +1347,14 @@ extern {
                                        length: libc::c_ulong,
                                        string: *const u16);
 
+    /// Return the Unicode string associated with a Quartz keyboard event,
+    /// copying at most `max_length` characters. Passing a null string returns
+    /// its length in `actual_length`.
+    fn CGEventKeyboardGetUnicodeString(event: ::sys::CGEventRef,
+                                       max_length: libc::c_ulong,
+                                       actual_length: *mut libc::c_ulong,
+                                       string: *mut u16);
+
     /// Return the integer value of a field in an event.
     fn CGEventGetIntegerValueField(event: ::sys::CGEventRef, field: CGEventField) -> i64;
 
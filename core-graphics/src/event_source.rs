@@ -1,5 +1,8 @@
 use core_foundation::base::{CFRelease, CFRetain, CFTypeID};
 use foreign_types::ForeignType;
+use libc;
+
+pub type CGEventSourceKeyboardType = libc::uint32_t;
 
 /// Possible source states of an event source.
 #[repr(C)]
@@ -36,6 +39,96 @@ impl CGEventSource {
             }
         }
     }
+
+    /// Returns the source state the event source was created with.
+    pub fn state_id(&self) -> CGEventSourceStateID {
+        match unsafe { CGEventSourceGetSourceStateID(self.as_ptr()) } {
+            -1 => CGEventSourceStateID::Private,
+            1 => CGEventSourceStateID::HIDSystemState,
+            _ => CGEventSourceStateID::CombinedSessionState,
+        }
+    }
+
+    /// Returns for how many seconds local hardware events are suppressed
+    /// after an event from this source is posted.
+    pub fn local_events_suppression_interval(&self) -> f64 {
+        unsafe {
+            CGEventSourceGetLocalEventsSuppressionInterval(self.as_ptr())
+        }
+    }
+
+    /// Sets for how many seconds local hardware events are suppressed after an
+    /// event from this source is posted, e.g. 0 so that synthesized events
+    /// don't lock out the user's own mouse movements.
+    pub fn set_local_events_suppression_interval(&self, seconds: f64) {
+        unsafe {
+            CGEventSourceSetLocalEventsSuppressionInterval(self.as_ptr(), seconds)
+        }
+    }
+
+    /// Returns how many pixels a scroll wheel event of one line scrolls.
+    pub fn pixels_per_line(&self) -> f64 {
+        unsafe {
+            CGEventSourceGetPixelsPerLine(self.as_ptr())
+        }
+    }
+
+    /// Sets how many pixels a scroll wheel event of one line created from this
+    /// source scrolls.
+    pub fn set_pixels_per_line(&self, pixels_per_line: f64) {
+        unsafe {
+            CGEventSourceSetPixelsPerLine(self.as_ptr(), pixels_per_line)
+        }
+    }
+
+    /// Returns the 64-bit user-specified data attached to this event source.
+    pub fn user_data(&self) -> i64 {
+        unsafe {
+            CGEventSourceGetUserData(self.as_ptr())
+        }
+    }
+
+    /// Sets the 64-bit user-specified data for this event source.
+    ///
+    /// Events created from this source carry the value in their
+    /// `EventField::EVENT_SOURCE_USER_DATA` field, which lets an event tap
+    /// recognize (and skip) events it synthesized itself.
+    pub fn set_user_data(&self, user_data: i64) {
+        unsafe {
+            CGEventSourceSetUserData(self.as_ptr(), user_data)
+        }
+    }
+
+    /// Returns the keyboard type to be used with this event source.
+    pub fn keyboard_type(&self) -> CGEventSourceKeyboardType {
+        unsafe {
+            CGEventSourceGetKeyboardType(self.as_ptr())
+        }
+    }
+
+    /// Sets the keyboard type to be used with this event source.
+    pub fn set_keyboard_type(&self, keyboard_type: CGEventSourceKeyboardType) {
+        unsafe {
+            CGEventSourceSetKeyboardType(self.as_ptr(), keyboard_type)
+        }
+    }
+}
+
+#[test]
+fn event_source_user_data_test() {
+    let source = CGEventSource::new(CGEventSourceStateID::Private).unwrap();
+    source.set_user_data(0x1234_5678_9abc);
+    assert_eq!(source.user_data(), 0x1234_5678_9abc);
+}
+
+#[test]
+fn event_source_settings_test() {
+    let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState).unwrap();
+    assert_eq!(source.state_id() as i32, CGEventSourceStateID::HIDSystemState as i32);
+    source.set_local_events_suppression_interval(0.);
+    assert_eq!(source.local_events_suppression_interval(), 0.);
+    source.set_pixels_per_line(12.);
+    assert_eq!(source.pixels_per_line(), 12.);
 }
 
 #[link(name = "CoreGraphics", kind = "framework")]
@@ -45,4 +138,35 @@ extern {
 
     /// Return a Quartz event source created with a specified source state.
     fn CGEventSourceCreate(stateID: CGEventSourceStateID) -> ::sys::CGEventSourceRef;
+
+    /// Return the source state associated with a Quartz event source.
+    fn CGEventSourceGetSourceStateID(source: ::sys::CGEventSourceRef) -> libc::int32_t;
+
+    /// Return the interval that local hardware events may be suppressed
+    /// following the posting of a Quartz event.
+    fn CGEventSourceGetLocalEventsSuppressionInterval(source: ::sys::CGEventSourceRef) -> f64;
+
+    /// Set the period of time in seconds that local hardware events may be
+    /// suppressed following the posting of a Quartz event.
+    fn CGEventSourceSetLocalEventsSuppressionInterval(source: ::sys::CGEventSourceRef,
+                                                      seconds: f64);
+
+    /// Gets the scale of pixels per line in a scrolling event source.
+    fn CGEventSourceGetPixelsPerLine(source: ::sys::CGEventSourceRef) -> f64;
+
+    /// Set the scale of pixels per line in a scrolling event source.
+    fn CGEventSourceSetPixelsPerLine(source: ::sys::CGEventSourceRef, pixelsPerLine: f64);
+
+    /// Return the 64-bit user-specified data for a Quartz event source.
+    fn CGEventSourceGetUserData(source: ::sys::CGEventSourceRef) -> i64;
+
+    /// Set the 64-bit user-specified data for a Quartz event source.
+    fn CGEventSourceSetUserData(source: ::sys::CGEventSourceRef, userData: i64);
+
+    /// Return the keyboard type to be used with a Quartz event source.
+    fn CGEventSourceGetKeyboardType(source: ::sys::CGEventSourceRef) -> CGEventSourceKeyboardType;
+
+    /// Set the keyboard type to be used with a Quartz event source.
+    fn CGEventSourceSetKeyboardType(source: ::sys::CGEventSourceRef,
+                                    keyboardType: CGEventSourceKeyboardType);
 }
@@ -15,6 +15,7 @@ use core_foundation::string::{CFString, CFStringRef};
 use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
 use data_provider::CGDataProvider;
 use geometry::CGRect;
+use base::CGFloat;
 
 use foreign_types::ForeignType;
 
@@ -102,6 +103,23 @@ impl CGFont {
         }
     }
 
+    /// Returns the advance of each glyph, in glyph space units.
+    pub fn glyph_advances(&self, glyphs: &[CGGlyph]) -> Vec<i32> {
+        let mut advances = vec![0; glyphs.len()];
+        if !self.get_glyph_advances(glyphs, &mut advances) {
+            advances.clear();
+        }
+        advances
+    }
+
+    /// Returns the width of the glyph run when set at `size` points, i.e. the sum of the glyph
+    /// advances scaled from glyph space to user space.
+    pub fn text_width(&self, glyphs: &[CGGlyph], size: CGFloat) -> CGFloat {
+        let units_per_em = self.get_units_per_em() as CGFloat;
+        let total: i64 = self.glyph_advances(glyphs).iter().map(|&a| a as i64).sum();
+        total as CGFloat * size / units_per_em
+    }
+
     pub fn get_units_per_em(&self) -> c_int {
         unsafe {
             CGFontGetUnitsPerEm(self.as_ptr())
@@ -124,6 +142,15 @@ impl CGFont {
     }
 }
 
+#[test]
+fn text_width_test() {
+    let font = CGFont::from_name(&CFString::from_static_string("Helvetica")).unwrap();
+    let two = font.text_width(&[0x24, 0x25], 12.);
+    let three = font.text_width(&[0x24, 0x25, 0x26], 12.);
+    assert!(two > 0.);
+    assert!(three > two);
+}
+
 #[link(name = "CoreGraphics", kind = "framework")]
 extern {
     // TODO: basically nothing has bindings (even commented-out) besides what we use.
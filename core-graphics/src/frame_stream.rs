@@ -0,0 +1,152 @@
+// Copyright 2018 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Display frames as an asynchronous stream, enabled by the `async` feature.
+
+#![allow(non_upper_case_globals)]
+
+use core_foundation::base::{CFRelease, CFRetain, CFType};
+use core_foundation::dictionary::CFDictionary;
+use core_foundation::string::CFString;
+use display::CGDisplay;
+use display_stream::{CGDisplayStream, IOSurfaceRef};
+use display_stream::{kCGDisplayStreamFrameStatusFrameComplete, kCGDisplayStreamFrameStatusStopped};
+use futures::channel::mpsc;
+use futures::{Stream, task};
+use geometry::CGRect;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+/// A frame of a display, see `FrameStream`.
+pub struct Frame {
+    surface: IOSurfaceRef,
+    /// The time the frame was displayed, in mach absolute time units.
+    pub display_time: u64,
+    /// The rects of the frame that changed since the previous frame, in pixels
+    /// with the origin at the top left.
+    pub dirty_rects: Vec<CGRect>,
+}
+
+impl Frame {
+    /// Returns the surface holding the frame. It isn't reused by the display
+    /// stream while the frame is alive.
+    pub fn surface(&self) -> IOSurfaceRef {
+        self.surface
+    }
+}
+
+// IOSurfaces can be used from any thread.
+unsafe impl Send for Frame {}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        unsafe {
+            IOSurfaceDecrementUseCount(self.surface);
+            CFRelease(self.surface as *const _);
+        }
+    }
+}
+
+/// A stream of the frames of a display.
+///
+/// At most `capacity` frames (but at least one) are buffered; while the
+/// consumer is behind, new frames are dropped rather than queued. The
+/// display stream is stopped when this is dropped.
+pub struct FrameStream {
+    stream: CGDisplayStream,
+    receiver: mpsc::Receiver<Frame>,
+}
+
+impl FrameStream {
+    /// Starts streaming the frames of `display`, with the same arguments as
+    /// `CGDisplayStream::new`. Returns `None` if the stream can't be created
+    /// or started.
+    pub fn new(display: &CGDisplay,
+               output_width: usize,
+               output_height: usize,
+               pixel_format: i32,
+               properties: Option<&CFDictionary<CFString, CFType>>,
+               capacity: usize)
+               -> Option<FrameStream> {
+        // The channel has an extra slot for its sender.
+        let (sender, receiver) = mpsc::channel(capacity.saturating_sub(1));
+        let sender = Mutex::new(Some(sender));
+        let stream = CGDisplayStream::new(display, output_width, output_height, pixel_format,
+                                          properties, move |status, display_time, surface, update| {
+            let mut sender = sender.lock().unwrap();
+            match (status, surface) {
+                (kCGDisplayStreamFrameStatusFrameComplete, Some(surface)) => {
+                    let sender = match *sender {
+                        Some(ref mut sender) => sender,
+                        None => return,
+                    };
+                    let frame = unsafe {
+                        CFRetain(surface as *const _);
+                        IOSurfaceIncrementUseCount(surface);
+                        Frame {
+                            surface: surface,
+                            display_time: display_time,
+                            dirty_rects: update.map_or(vec![], |update| update.dirty_rects()),
+                        }
+                    };
+                    // Dropping the frame when the buffer is full is the
+                    // backpressure.
+                    let _ = sender.try_send(frame);
+                }
+                // Ends the stream.
+                (kCGDisplayStreamFrameStatusStopped, _) => *sender = None,
+                _ => {}
+            }
+        });
+        let stream = match stream {
+            Some(stream) => stream,
+            None => return None,
+        };
+        if stream.start().is_err() {
+            return None;
+        }
+        Some(FrameStream {
+            stream: stream,
+            receiver: receiver,
+        })
+    }
+}
+
+impl Stream for FrameStream {
+    type Item = Frame;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut task::Context) -> task::Poll<Option<Frame>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+impl Drop for FrameStream {
+    fn drop(&mut self) {
+        let _ = self.stream.stop();
+    }
+}
+
+#[test]
+#[ignore] // needs permission to record the screen
+fn frame_stream_test() {
+    use display_stream::kCVPixelFormatType_32BGRA;
+    use futures::executor::block_on;
+    use futures::StreamExt;
+
+    let mut frames = FrameStream::new(&CGDisplay::main(), 64, 64, kCVPixelFormatType_32BGRA,
+                                      None, 2).unwrap();
+    let frame = block_on(frames.next()).unwrap();
+    assert!(!frame.surface().is_null());
+}
+
+#[link(name = "IOSurface", kind = "framework")]
+extern {
+    fn IOSurfaceIncrementUseCount(buffer: IOSurfaceRef);
+    fn IOSurfaceDecrementUseCount(buffer: IOSurfaceRef);
+}
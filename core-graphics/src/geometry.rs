@@ -80,6 +80,23 @@ impl CGPoint {
     }
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CGVector {
+    pub dx: CGFloat,
+    pub dy: CGFloat,
+}
+
+impl CGVector {
+    #[inline]
+    pub fn new(dx: CGFloat, dy: CGFloat) -> CGVector {
+        CGVector {
+            dx: dx,
+            dy: dy,
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default)]
 pub struct CGRect {
@@ -132,6 +149,20 @@ impl CGRect {
         }
     }
 
+    #[inline]
+    pub fn intersection(&self, other: &CGRect) -> CGRect {
+        unsafe {
+            ffi::CGRectIntersection(*self, *other)
+        }
+    }
+
+    #[inline]
+    pub fn union(&self, other: &CGRect) -> CGRect {
+        unsafe {
+            ffi::CGRectUnion(*self, *other)
+        }
+    }
+
     #[inline]
     pub fn apply_transform(&self, t: &CGAffineTransform) -> CGRect {
         unsafe {
@@ -184,6 +215,8 @@ mod ffi {
                                                       rect: *mut CGRect) -> boolean_t;
         pub fn CGRectIsEmpty(rect: CGRect) -> boolean_t;
         pub fn CGRectIntersectsRect(rect1: CGRect, rect2: CGRect) -> boolean_t;
+        pub fn CGRectIntersection(rect1: CGRect, rect2: CGRect) -> CGRect;
+        pub fn CGRectUnion(rect1: CGRect, rect2: CGRect) -> CGRect;
 
         pub fn CGAffineTransformInvert(t: CGAffineTransform) -> CGAffineTransform;
 
@@ -0,0 +1,73 @@
+// Copyright 2018 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![allow(non_upper_case_globals)]
+
+use base::CGFloat;
+use color_space::CGColorSpace;
+use core_foundation::base::{CFRelease, CFRetain, CFTypeID};
+use foreign_types::ForeignType;
+use libc::size_t;
+
+/// Whether a gradient is extended beyond its start and end, see
+/// `CGContext::draw_linear_gradient`.
+pub type CGGradientDrawingOptions = u32;
+
+pub const kCGGradientDrawsBeforeStartLocation: CGGradientDrawingOptions = 1 << 0;
+pub const kCGGradientDrawsAfterEndLocation: CGGradientDrawingOptions = 1 << 1;
+
+foreign_type! {
+    #[doc(hidden)]
+    type CType = ::sys::CGGradient;
+    fn drop = |p| CFRelease(p as *mut _);
+    fn clone = |p| CFRetain(p as *const _) as *mut _;
+    pub struct CGGradient;
+    pub struct CGGradientRef;
+}
+
+impl CGGradient {
+    pub fn type_id() -> CFTypeID {
+        unsafe {
+            CGGradientGetTypeID()
+        }
+    }
+
+    /// Creates a gradient in `space` through a color at each of `locations`, which run from
+    /// 0 at the start of the gradient to 1 at its end.
+    ///
+    /// `components` holds the components of each color followed by its alpha.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `components` doesn't hold one color per location.
+    pub fn create_with_color_components(space: &CGColorSpace,
+                                        components: &[CGFloat],
+                                        locations: &[CGFloat])
+                                        -> CGGradient {
+        assert_eq!(components.len(), locations.len() * (space.number_of_components() + 1));
+        unsafe {
+            let result = CGGradientCreateWithColorComponents(space.as_ptr(),
+                                                             components.as_ptr(),
+                                                             locations.as_ptr(),
+                                                             locations.len());
+            assert!(!result.is_null());
+            CGGradient::from_ptr(result)
+        }
+    }
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern {
+    fn CGGradientGetTypeID() -> CFTypeID;
+    fn CGGradientCreateWithColorComponents(space: ::sys::CGColorSpaceRef,
+                                           components: *const CGFloat,
+                                           locations: *const CGFloat,
+                                           count: size_t)
+                                           -> ::sys::CGGradientRef;
+}
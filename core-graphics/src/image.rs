@@ -1,7 +1,20 @@
+use base::{CGFloat, kCGBitmapByteOrder32Host, kCGBitmapByteOrderMask};
+use base::{kCGImageAlphaLast, kCGImageAlphaNone, kCGImageAlphaPremultipliedFirst};
+use base::kCGImageAlphaPremultipliedLast;
+use context::{CGContext, CGInterpolationQuality};
+use geometry::{CGAffineTransform, CGPoint, CGRect, CGSize};
+use std::cmp;
+use std::ptr;
+use std::sync::{Arc, Mutex};
 use core_foundation::base::{CFRetain, CFTypeID};
 use core_foundation::data::CFData;
-use color_space::CGColorSpace;
-use data_provider::CGDataProviderRef;
+use core_foundation::url::CFURL;
+use color_space::{CGColorRenderingIntent, CGColorSpace};
+use data_consumer::CGDataConsumer;
+use data_provider::{CGDataProvider, CGDataProviderRef};
+use image_io::{CGImageDestination, ImageFormat};
+#[cfg(target_os = "macos")]
+use display::CGDisplay;
 use libc::size_t;
 use foreign_types::{ForeignType, ForeignTypeRef};
 
@@ -26,6 +39,26 @@ pub enum CGImageByteOrderInfo {
     CGImageByteOrder32Big = (4 << 12)
 }
 
+/// How an image is fitted into a target size with a different aspect ratio.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResizeMode {
+    /// Scale each axis independently to fill the target exactly.
+    Stretch,
+    /// Scale uniformly so the whole image fits, leaving transparent bars.
+    AspectFit,
+    /// Scale uniformly so the image covers the target, cropping the overflow.
+    AspectFill,
+}
+
+/// A channel of an RGBA image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
 foreign_type! {
     #[doc(hidden)]
     type CType = ::sys::CGImage;
@@ -36,11 +69,133 @@ foreign_type! {
 }
 
 impl CGImage {
+    pub fn new(width: size_t,
+               height: size_t,
+               bits_per_component: size_t,
+               bits_per_pixel: size_t,
+               bytes_per_row: size_t,
+               colorspace: &CGColorSpace,
+               bitmap_info: u32,
+               provider: &CGDataProvider,
+               should_interpolate: bool,
+               rendering_intent: CGColorRenderingIntent)
+               -> Self {
+        unsafe {
+            let result = CGImageCreate(width,
+                                       height,
+                                       bits_per_component,
+                                       bits_per_pixel,
+                                       bytes_per_row,
+                                       colorspace.as_ptr(),
+                                       bitmap_info,
+                                       provider.as_ptr(),
+                                       ptr::null_mut(),
+                                       should_interpolate,
+                                       rendering_intent);
+            assert!(!result.is_null());
+            Self::from_ptr(result)
+        }
+    }
+
+    /// Creates an image mask: a grayscale image without a color space whose samples are
+    /// inverse alpha, so 0 paints and the maximum sample value masks out. It can be used
+    /// with `with_mask` or `CGContext::clip_to_mask`.
+    pub fn new_mask(width: size_t,
+                    height: size_t,
+                    bits_per_component: size_t,
+                    bits_per_pixel: size_t,
+                    bytes_per_row: size_t,
+                    provider: &CGDataProvider,
+                    should_interpolate: bool)
+                    -> Option<CGImage> {
+        unsafe {
+            let result = CGImageMaskCreate(width,
+                                           height,
+                                           bits_per_component,
+                                           bits_per_pixel,
+                                           bytes_per_row,
+                                           provider.as_ptr(),
+                                           ptr::null(),
+                                           should_interpolate);
+            if !result.is_null() {
+                Some(Self::from_ptr(result))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Creates an sRGB image from 8-bit RGBA pixels with straight (not premultiplied) alpha,
+    /// in rows from top to bottom with no padding. The pixels are copied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` isn't `width * height * 4` bytes long.
+    pub fn from_rgba8(data: &[u8], width: size_t, height: size_t) -> CGImage {
+        assert_eq!(data.len(), width * height * 4, "wrong buffer size for a {}x{} image",
+                   width, height);
+        let provider = CGDataProvider::from_buffer(Arc::new(data.to_vec()));
+        CGImage::new(width,
+                     height,
+                     8,
+                     32,
+                     width * 4,
+                     &CGColorSpace::create_srgb(),
+                     kCGImageAlphaLast,
+                     &provider,
+                     false,
+                     CGColorRenderingIntent::Default)
+    }
+
     pub fn type_id() -> CFTypeID {
         unsafe {
             CGImageGetTypeID()
         }
     }
+
+    /// Packs `images` into a single RGBA image no wider than `max_width` pixels, using a simple
+    /// shelf layout in the order given.
+    ///
+    /// Returns the atlas along with the rect each image occupies in it. The rects are in pixels
+    /// with the origin at the top-left corner of the atlas, as expected by `CGImage` cropping.
+    /// Returns `None` if `images` is empty, any image is wider than `max_width` or all of them
+    /// are empty.
+    pub fn pack_atlas(images: &[CGImage], max_width: usize) -> Option<(CGImage, Vec<CGRect>)> {
+        if images.is_empty() || images.iter().any(|image| image.width() > max_width) {
+            return None;
+        }
+
+        let mut rects = Vec::with_capacity(images.len());
+        let (mut x, mut y, mut shelf_height, mut width) = (0, 0, 0, 0);
+        for image in images {
+            if x + image.width() > max_width {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+            rects.push(CGRect::new(&CGPoint::new(x as CGFloat, y as CGFloat),
+                                   &CGSize::new(image.width() as CGFloat,
+                                                image.height() as CGFloat)));
+            x += image.width();
+            width = cmp::max(width, x);
+            shelf_height = cmp::max(shelf_height, image.height());
+        }
+        let height = y + shelf_height;
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let context = CGContext::create_bitmap_context(None, width, height, 8, 0,
+                                                       &CGColorSpace::create_device_rgb(),
+                                                       kCGImageAlphaPremultipliedLast);
+        for (image, rect) in images.iter().zip(&rects) {
+            // Flip into the context's bottom-left origin coordinate space.
+            let mut draw_rect = *rect;
+            draw_rect.origin.y = height as CGFloat - rect.origin.y - rect.size.height;
+            context.draw_image(draw_rect, image);
+        }
+        context.create_image().map(|atlas| (atlas, rects))
+    }
 }
 
 impl CGImageRef {
@@ -74,6 +229,401 @@ impl CGImageRef {
         }
     }
 
+    pub fn bitmap_info(&self) -> u32 {
+        unsafe {
+            CGImageGetBitmapInfo(self.as_ptr())
+        }
+    }
+
+    /// Returns the alpha layout of the pixels, one of the `kCGImageAlpha*` constants, without
+    /// the byte order and float flags of `bitmap_info`.
+    pub fn alpha_info(&self) -> u32 {
+        unsafe {
+            CGImageGetAlphaInfo(self.as_ptr())
+        }
+    }
+
+    /// Returns true if `other` has the same dimensions, pixel size and byte order as this image,
+    /// so that their pixel data can be compared or combined byte for byte.
+    pub fn compatible_with(&self, other: &CGImageRef) -> bool {
+        self.width() == other.width() &&
+            self.height() == other.height() &&
+            self.bits_per_pixel() == other.bits_per_pixel() &&
+            self.bitmap_info() & kCGBitmapByteOrderMask ==
+                other.bitmap_info() & kCGBitmapByteOrderMask
+    }
+
+    /// Returns the part of the image within `rect`, which is in pixels with the origin at the
+    /// top-left corner of the image, e.g. to cut a window or region out of a screenshot. The
+    /// pixels aren't copied; the cropped image shares them with this one, so this is cheap.
+    ///
+    /// `rect` is rounded out to whole pixels and clipped to the image. Returns `None` if it
+    /// doesn't overlap the image.
+    pub fn cropped_to(&self, rect: CGRect) -> Option<CGImage> {
+        unsafe {
+            let image = CGImageCreateWithImageInRect(self.as_ptr(), rect);
+            if !image.is_null() {
+                Some(CGImage::from_ptr(image))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns a copy of the image mirrored top to bottom.
+    pub fn flipped_vertically(&self) -> Option<CGImage> {
+        let height = self.height() as CGFloat;
+        self.redraw(self.width(), self.height(), |context| {
+            context.translate(0., height);
+            context.scale(1., -1.);
+        })
+    }
+
+    /// Returns a copy of the image mirrored left to right.
+    pub fn flipped_horizontally(&self) -> Option<CGImage> {
+        let width = self.width() as CGFloat;
+        self.redraw(self.width(), self.height(), |context| {
+            context.translate(width, 0.);
+            context.scale(-1., 1.);
+        })
+    }
+
+    /// Returns an RGBA copy of the image with non-premultiplied ("straight") alpha, i.e. with
+    /// `kCGImageAlphaLast` alpha info, for libraries that can't handle premultiplied pixels.
+    ///
+    /// Bitmap contexts only support premultiplied alpha, so the image is drawn premultiplied and
+    /// then divided through by alpha. Color precision is lost in mostly transparent pixels.
+    pub fn to_straight_alpha(&self) -> Option<CGImage> {
+        let premultiplied = match self.redraw(self.width(), self.height(), |_| {}) {
+            Some(image) => image,
+            None => return None,
+        };
+        let mut pixels = premultiplied.data().bytes().to_vec();
+        for pixel in pixels.chunks_mut(4) {
+            let alpha = pixel[3] as u32;
+            if alpha == 0 || alpha == 255 {
+                continue;
+            }
+            for component in &mut pixel[..3] {
+                *component = cmp::min(255, (*component as u32 * 255 + alpha / 2) / alpha) as u8;
+            }
+        }
+        let provider = CGDataProvider::from_buffer(Arc::new(pixels));
+        Some(CGImage::new(premultiplied.width(),
+                          premultiplied.height(),
+                          8,
+                          32,
+                          premultiplied.bytes_per_row(),
+                          &CGColorSpace::create_device_rgb(),
+                          kCGImageAlphaLast,
+                          &provider,
+                          false,
+                          CGColorRenderingIntent::Default))
+    }
+
+    /// Resizes the image to the native pixel resolution of `display`'s current mode, in the
+    /// display's color space, e.g. to use it as the display's wallpaper.
+    #[cfg(target_os = "macos")]
+    pub fn fit_to_display(&self, display: &CGDisplay, mode: ResizeMode) -> Option<CGImage> {
+        let (width, height) = match display.display_mode() {
+            Some(display_mode) => (display_mode.pixel_width() as usize,
+                                   display_mode.pixel_height() as usize),
+            None => (display.pixels_wide() as usize, display.pixels_high() as usize),
+        };
+        if width == 0 || height == 0 || self.width() == 0 || self.height() == 0 {
+            return None;
+        }
+        let space = display.color_space().unwrap_or_else(CGColorSpace::create_device_rgb);
+        let context = CGContext::create_bitmap_context(None, width, height, 8, 0, &space,
+                                                       kCGImageAlphaPremultipliedFirst |
+                                                       kCGBitmapByteOrder32Host);
+
+        let (width, height) = (width as CGFloat, height as CGFloat);
+        let x_scale = width / self.width() as CGFloat;
+        let y_scale = height / self.height() as CGFloat;
+        let (x_scale, y_scale) = match mode {
+            ResizeMode::Stretch => (x_scale, y_scale),
+            ResizeMode::AspectFit => (x_scale.min(y_scale), x_scale.min(y_scale)),
+            ResizeMode::AspectFill => (x_scale.max(y_scale), x_scale.max(y_scale)),
+        };
+        let size = CGSize::new(self.width() as CGFloat * x_scale,
+                               self.height() as CGFloat * y_scale);
+        let origin = CGPoint::new((width - size.width) / 2., (height - size.height) / 2.);
+        context.draw_image(CGRect::new(&origin, &size), &self.to_owned());
+        context.create_image()
+    }
+
+    /// Returns a copy of the image rotated clockwise by `quarter_turns` quarter turns, e.g. to
+    /// undo the rotation of a display or apply an EXIF orientation.
+    ///
+    /// Returns `None` unless `quarter_turns` is less than 4.
+    pub fn rotated(&self, quarter_turns: u8) -> Option<CGImage> {
+        let (width, height) = (self.width(), self.height());
+        let (w, h) = (width as CGFloat, height as CGFloat);
+        // Exact matrices, so that pixels land on pixels.
+        let (transform, new_width, new_height) = match quarter_turns {
+            0 => (CGAffineTransform { a: 1., b: 0., c: 0., d: 1., tx: 0., ty: 0. }, width, height),
+            1 => (CGAffineTransform { a: 0., b: -1., c: 1., d: 0., tx: 0., ty: w }, height, width),
+            2 => (CGAffineTransform { a: -1., b: 0., c: 0., d: -1., tx: w, ty: h }, width, height),
+            3 => (CGAffineTransform { a: 0., b: 1., c: -1., d: 0., tx: h, ty: 0. }, height, width),
+            _ => return None,
+        };
+        self.redraw(new_width, new_height, |context| context.concat_ctm(transform))
+    }
+
+    /// Returns an 8-bit grayscale image of a single channel of the image, e.g. to inspect the
+    /// alpha of a capture or to build a mask from a color channel.
+    ///
+    /// Color channels are extracted without alpha premultiplication.
+    pub fn extract_channel(&self, channel: Channel) -> Option<CGImage> {
+        let rgba = match self.redraw(self.width(), self.height(), |_| {}) {
+            Some(rgba) => rgba,
+            None => return None,
+        };
+        let (width, height) = (rgba.width(), rgba.height());
+        let index = match channel {
+            Channel::Red => 0,
+            Channel::Green => 1,
+            Channel::Blue => 2,
+            Channel::Alpha => 3,
+        };
+        let data = rgba.data();
+        let mut gray = Vec::with_capacity(width * height);
+        for row in data.bytes().chunks(rgba.bytes_per_row()) {
+            for pixel in row[..width * 4].chunks(4) {
+                let (value, alpha) = (pixel[index] as u32, pixel[3] as u32);
+                gray.push(if index == 3 || alpha == 0 || alpha == 255 {
+                    value as u8
+                } else {
+                    cmp::min(255, (value * 255 + alpha / 2) / alpha) as u8
+                });
+            }
+        }
+        let provider = CGDataProvider::from_buffer(Arc::new(gray));
+        Some(CGImage::new(width,
+                          height,
+                          8,
+                          8,
+                          width,
+                          &CGColorSpace::create_device_gray(),
+                          kCGImageAlphaNone,
+                          &provider,
+                          false,
+                          CGColorRenderingIntent::Default))
+    }
+
+    /// Returns a quick, low-quality preview of the image made by taking every `factor`-th pixel
+    /// in each direction, without any filtering.
+    ///
+    /// Each dimension is divided by `factor`, rounding down (but to no less than one pixel).
+    /// Returns `None` if `factor` is 0.
+    pub fn subsampled(&self, factor: usize) -> Option<CGImage> {
+        if factor == 0 {
+            return None;
+        }
+        let width = cmp::max(self.width() / factor, 1);
+        let height = cmp::max(self.height() / factor, 1);
+        let scale = 1. / factor as CGFloat;
+        self.redraw(width, height, |context| {
+            context.set_interpolation_quality(CGInterpolationQuality::None);
+            context.scale(scale, scale);
+        })
+    }
+
+    /// Returns up to `k` dominant colors of the image, most common first, found by k-means
+    /// clustering of its (non-transparent) pixels.
+    ///
+    /// The image is first downscaled so that at most 64x64 pixels are sampled.
+    pub fn dominant_colors(&self, k: usize) -> Vec<(u8, u8, u8)> {
+        const MAX_SAMPLE_SIZE: usize = 64;
+        const ITERATIONS: usize = 10;
+
+        if k == 0 || self.width() == 0 || self.height() == 0 {
+            return vec![];
+        }
+        let width = cmp::min(self.width(), MAX_SAMPLE_SIZE);
+        let height = cmp::min(self.height(), MAX_SAMPLE_SIZE);
+        let x_scale = width as CGFloat / self.width() as CGFloat;
+        let y_scale = height as CGFloat / self.height() as CGFloat;
+        let sample = match self.redraw(width, height, |context| context.scale(x_scale, y_scale)) {
+            Some(sample) => sample,
+            None => return vec![],
+        };
+
+        let data = sample.data();
+        let bytes_per_row = sample.bytes_per_row();
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in data.bytes().chunks(bytes_per_row) {
+            for pixel in row[..width * 4].chunks(4) {
+                let alpha = pixel[3] as f64;
+                if alpha > 0. {
+                    let scale = 255. / alpha;
+                    pixels.push([pixel[0] as f64 * scale,
+                                 pixel[1] as f64 * scale,
+                                 pixel[2] as f64 * scale]);
+                }
+            }
+        }
+        if pixels.is_empty() {
+            return vec![];
+        }
+
+        fn distance(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+            (0..3).map(|i| (a[i] - b[i]) * (a[i] - b[i])).sum()
+        }
+        fn nearest(centers: &[[f64; 3]], pixel: &[f64; 3]) -> usize {
+            (0..centers.len()).min_by(|&a, &b| {
+                distance(&centers[a], pixel).partial_cmp(&distance(&centers[b], pixel)).unwrap()
+            }).unwrap()
+        }
+
+        // Seed deterministically with the pixels farthest from the centers chosen so far.
+        let mut centers = vec![pixels[0]];
+        while centers.len() < k {
+            let farthest = pixels.iter().max_by(|a, b| {
+                let a = distance(&centers[nearest(&centers, a)], a);
+                let b = distance(&centers[nearest(&centers, b)], b);
+                a.partial_cmp(&b).unwrap()
+            }).unwrap();
+            if distance(&centers[nearest(&centers, farthest)], farthest) == 0. {
+                break;
+            }
+            centers.push(*farthest);
+        }
+
+        let mut counts = vec![0; centers.len()];
+        for _ in 0..ITERATIONS {
+            let mut sums = vec![[0.; 3]; centers.len()];
+            counts = vec![0; centers.len()];
+            for pixel in &pixels {
+                let cluster = nearest(&centers, pixel);
+                for i in 0..3 {
+                    sums[cluster][i] += pixel[i];
+                }
+                counts[cluster] += 1;
+            }
+            for (center, (sum, &count)) in centers.iter_mut().zip(sums.iter().zip(&counts)) {
+                if count > 0 {
+                    *center = [sum[0] / count as f64, sum[1] / count as f64, sum[2] / count as f64];
+                }
+            }
+        }
+
+        let mut clusters: Vec<_> = centers.into_iter().zip(counts).filter(|c| c.1 > 0).collect();
+        clusters.sort_by(|a, b| b.1.cmp(&a.1));
+        clusters.into_iter().map(|(center, _)| {
+            let channel = |value: f64| value.round().max(0.).min(255.) as u8;
+            (channel(center[0]), channel(center[1]), channel(center[2]))
+        }).collect()
+    }
+
+    /// Encodes the image in `format` and appends the encoded file to `out`, e.g. to save a
+    /// screenshot as a PNG. Returns `false` if the image could not be encoded.
+    pub fn encode(&self, format: ImageFormat, out: &mut Vec<u8>) -> bool {
+        let buffer = Arc::new(Mutex::new(vec![]));
+        {
+            let consumer = CGDataConsumer::from_buffer(buffer.clone());
+            let destination = match CGImageDestination::from_consumer(&consumer,
+                                                                      &format.type_identifier(),
+                                                                      1) {
+                Some(destination) => destination,
+                None => return false,
+            };
+            destination.add_image(&self.to_owned(), None);
+            if !destination.finalize() {
+                return false;
+            }
+        }
+        let encoded = buffer.lock().unwrap();
+        out.extend_from_slice(&encoded);
+        true
+    }
+
+    /// Encodes the image in `format` and writes it to the file at `url`. Returns `false` if
+    /// the image could not be encoded or written.
+    pub fn write_to_url(&self, url: &CFURL, format: ImageFormat) -> bool {
+        match CGImageDestination::from_url(url, &format.type_identifier(), 1) {
+            Some(destination) => {
+                destination.add_image(&self.to_owned(), None);
+                destination.finalize()
+            }
+            None => false,
+        }
+    }
+
+    // Draws the image into a new `width` x `height` RGBA bitmap context, after `transform` has
+    // set up the context's CTM, and returns the result. Returns `None` for an empty size, as
+    // there can't be an empty bitmap context.
+    fn redraw<F>(&self, width: usize, height: usize, transform: F) -> Option<CGImage>
+                 where F: FnOnce(&CGContext) {
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let context = CGContext::create_bitmap_context(None, width, height, 8, 0,
+                                                       &CGColorSpace::create_device_rgb(),
+                                                       kCGImageAlphaPremultipliedLast);
+        transform(&context);
+        let rect = CGRect::new(&CGPoint::new(0., 0.),
+                               &CGSize::new(self.width() as CGFloat, self.height() as CGFloat));
+        context.draw_image(rect, &self.to_owned());
+        context.create_image()
+    }
+
+    /// Returns the image masked by `mask`, stretched to the size of the image: either an
+    /// image mask made with `new_mask`, or a grayscale image whose samples act as alpha.
+    ///
+    /// Returns `None` if the image is itself a mask or has alpha it can't combine with the
+    /// mask.
+    pub fn with_mask(&self, mask: &CGImage) -> Option<CGImage> {
+        unsafe {
+            let image = CGImageCreateWithMask(self.as_ptr(), mask.as_ptr());
+            if !image.is_null() {
+                Some(CGImage::from_ptr(image))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the image with the pixels whose color is within the given ranges masked out,
+    /// e.g. to color-key a background. `ranges` holds a minimum and maximum for each color
+    /// component, in the range of the image's samples, e.g. 0 to 255 for 8-bit components.
+    ///
+    /// Returns `None` if the image is a mask, has alpha or `ranges` doesn't cover every
+    /// component.
+    pub fn with_masking_colors(&self, ranges: &[CGFloat]) -> Option<CGImage> {
+        unsafe {
+            // Image masks have no color space.
+            let space = CGImageGetColorSpace(self.as_ptr());
+            if space.is_null() ||
+                    ranges.len() != CGColorSpaceGetNumberOfComponents(space) * 2 {
+                return None;
+            }
+            let image = CGImageCreateWithMaskingColors(self.as_ptr(), ranges.as_ptr());
+            if !image.is_null() {
+                Some(CGImage::from_ptr(image))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns a copy of the image whose pixels are interpreted in `space` instead, e.g. to
+    /// tag an untagged screenshot with the display's color space. The pixels themselves
+    /// aren't converted.
+    ///
+    /// Returns `None` if `space` doesn't have as many components as the image's color space.
+    pub fn with_color_space(&self, space: &CGColorSpace) -> Option<CGImage> {
+        unsafe {
+            let image = CGImageCreateCopyWithColorSpace(self.as_ptr(), space.as_ptr());
+            if !image.is_null() {
+                Some(CGImage::from_ptr(image))
+            } else {
+                None
+            }
+        }
+    }
+
     pub fn color_space(&self) -> CGColorSpace {
         unsafe {
             let cs = CGImageGetColorSpace(self.as_ptr());
@@ -84,6 +634,9 @@ impl CGImageRef {
 
     /// Returns the raw image bytes wrapped in `CFData`. Note, the returned `CFData` owns the
     /// underlying buffer.
+    ///
+    /// The bytes are `height` rows of `bytes_per_row` bytes, which may include padding past
+    /// `width` pixels of `bits_per_pixel` bits, laid out as given by `bitmap_info`.
     pub fn data(&self) -> CFData {
         let data_provider = unsafe {
             CGDataProviderRef::from_ptr(CGImageGetDataProvider(self.as_ptr()))
@@ -92,18 +645,346 @@ impl CGImageRef {
     }
 }
 
+#[test]
+fn encode_test() {
+    use image_io::CGImageSource;
+
+    let ctx = CGContext::create_argb_bitmap(5, 3);
+    let image = ctx.create_image().unwrap();
+    let mut png = vec![];
+    assert!(image.encode(ImageFormat::Png, &mut png));
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+    let decoded = CGImageSource::from_data(&CFData::from_buffer(&png)).unwrap();
+    let decoded = decoded.image_at_index(0).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (5, 3));
+
+    let path = ::std::env::temp_dir().join("core-graphics-encode-test.jpg");
+    let url = CFURL::from_path(&path, false).unwrap();
+    assert!(image.write_to_url(&url, ImageFormat::Jpeg));
+    assert!(::std::fs::metadata(&path).unwrap().len() > 0);
+    let _ = ::std::fs::remove_file(path);
+}
+
+#[test]
+fn from_rgba8_test() {
+    let image = CGImage::from_rgba8(&[255, 0, 0, 255, 0, 0, 255, 128], 2, 1);
+    assert_eq!((image.width(), image.height()), (2, 1));
+    assert_eq!(image.alpha_info(), kCGImageAlphaLast);
+    assert_eq!(image.data().bytes(), &[255, 0, 0, 255, 0, 0, 255, 128]);
+
+    let ctx = CGContext::create_argb_bitmap(2, 1);
+    ctx.draw_image(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(2., 1.)), &image);
+    assert_eq!(ctx.get_pixel(0, 0).unwrap(), [255, 0, 0, 255]);
+}
+
+#[test]
+#[should_panic(expected = "wrong buffer size")]
+fn from_rgba8_size_test() {
+    CGImage::from_rgba8(&[0; 12], 2, 2);
+}
+
+#[test]
+fn redraw_empty_test() {
+    let image = CGImage::from_rgba8(&[0; 4], 1, 1);
+    assert!(image.redraw(0, 1, |_| {}).is_none());
+    assert!(image.redraw(1, 0, |_| {}).is_none());
+    assert!(image.redraw(1, 1, |_| {}).is_some());
+}
+
+#[test]
+fn cropped_to_test() {
+    let red = [255, 0, 0, 255];
+    let green = [0, 255, 0, 255];
+    let blue = [0, 0, 255, 255];
+    let pixels: Vec<u8> = [red, red, red, red, green, blue].iter().flat_map(|p| p.to_vec())
+        .collect();
+    let image = CGImage::from_rgba8(&pixels, 3, 2);
+
+    let cropped = image.cropped_to(CGRect::new(&CGPoint::new(1., 1.), &CGSize::new(2., 1.)))
+        .unwrap();
+    assert_eq!((cropped.width(), cropped.height()), (2, 1));
+    let ctx = CGContext::create_argb_bitmap(2, 1);
+    ctx.draw_image(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(2., 1.)), &cropped);
+    assert_eq!(ctx.get_pixel(0, 0).unwrap(), green);
+    assert_eq!(ctx.get_pixel(1, 0).unwrap(), blue);
+
+    assert!(image.cropped_to(CGRect::new(&CGPoint::new(5., 5.), &CGSize::new(1., 1.))).is_none());
+}
+
+#[test]
+fn with_color_space_test() {
+    let image = CGImage::from_rgba8(&[1, 2, 3, 255], 1, 1);
+    let device = image.with_color_space(&CGColorSpace::create_device_rgb()).unwrap();
+    assert!(device.color_space() == CGColorSpace::create_device_rgb());
+    assert_eq!(device.data().bytes(), &[1, 2, 3, 255]);
+    assert!(image.with_color_space(&CGColorSpace::create_device_gray()).is_none());
+}
+
+#[test]
+fn with_mask_test() {
+    let provider = CGDataProvider::from_buffer(Arc::new(vec![255; 2 * 3]));
+    let image = CGImage::new(2, 1, 8, 24, 6, &CGColorSpace::create_device_rgb(), kCGImageAlphaNone,
+                             &provider, false, CGColorRenderingIntent::Default);
+    // Paints the left pixel and masks out the right one.
+    let provider = CGDataProvider::from_buffer(Arc::new(vec![0, 255]));
+    let mask = CGImage::new_mask(2, 1, 8, 8, 2, &provider, false).unwrap();
+    let masked = image.with_mask(&mask).unwrap();
+
+    let ctx = CGContext::create_argb_bitmap(2, 1);
+    ctx.draw_image(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(2., 1.)), &masked);
+    assert_eq!(ctx.get_pixel(0, 0).unwrap(), [255, 255, 255, 255]);
+    assert_eq!(ctx.get_pixel(1, 0).unwrap(), [0, 0, 0, 0]);
+}
+
+#[test]
+fn with_masking_colors_test() {
+    let pixels = vec![0, 255, 0, 255, 0, 0];
+    let provider = CGDataProvider::from_buffer(Arc::new(pixels));
+    let image = CGImage::new(2, 1, 8, 24, 6, &CGColorSpace::create_device_rgb(), kCGImageAlphaNone,
+                             &provider, false, CGColorRenderingIntent::Default);
+    assert!(image.with_masking_colors(&[0., 0.]).is_none());
+    let provider = CGDataProvider::from_buffer(Arc::new(vec![0, 255]));
+    let mask = CGImage::new_mask(2, 1, 8, 8, 2, &provider, false).unwrap();
+    assert!(mask.with_masking_colors(&[0., 0.]).is_none());
+    // Keys out pure green.
+    let keyed = image.with_masking_colors(&[0., 0., 255., 255., 0., 0.]).unwrap();
+
+    let ctx = CGContext::create_argb_bitmap(2, 1);
+    ctx.draw_image(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(2., 1.)), &keyed);
+    assert_eq!(ctx.get_pixel(0, 0).unwrap(), [0, 0, 0, 0]);
+    assert_eq!(ctx.get_pixel(1, 0).unwrap()[3], 255);
+}
+
+#[test]
+fn data_test() {
+    use geometry::{CGPoint, CGSize};
+
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None, 3, 2, 8, 0, &cs,
+                                               kCGImageAlphaPremultipliedLast);
+    ctx.set_rgb_fill_color(0., 0., 1., 1.);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(2., 0.), &CGSize::new(1., 1.)));
+    let image = ctx.create_image().unwrap();
+    assert_eq!(image.alpha_info(), kCGImageAlphaPremultipliedLast);
+    assert_eq!(image.bits_per_pixel(), 32);
+
+    let data = image.data();
+    assert_eq!(data.len() as usize, image.bytes_per_row() * 2);
+    // The bottom right pixel, in the second row in memory.
+    let offset = image.bytes_per_row() + 2 * 4;
+    assert_eq!(&data.bytes()[offset..offset + 4], &[0, 0, 255, 255]);
+    assert_eq!(&data.bytes()[..4], &[0, 0, 0, 0]);
+}
+
+#[test]
+fn compatible_with_test() {
+    let cs = CGColorSpace::create_device_rgb();
+    let small = CGContext::create_bitmap_context(None, 8, 8, 8, 0, &cs,
+                                                 kCGImageAlphaPremultipliedLast);
+    let large = CGContext::create_bitmap_context(None, 16, 8, 8, 0, &cs,
+                                                 kCGImageAlphaPremultipliedLast);
+    let small = small.create_image().unwrap();
+    let large = large.create_image().unwrap();
+    assert!(small.compatible_with(&small));
+    assert!(!small.compatible_with(&large));
+}
+
+#[test]
+fn pack_atlas_test() {
+    let cs = CGColorSpace::create_device_rgb();
+    let images: Vec<_> = (0..3).map(|_| {
+        let ctx = CGContext::create_bitmap_context(None, 16, 16, 8, 0, &cs,
+                                                   kCGImageAlphaPremultipliedLast);
+        ctx.create_image().unwrap()
+    }).collect();
+
+    let (atlas, rects) = CGImage::pack_atlas(&images, 40).unwrap();
+    assert_eq!(atlas.width(), 32);
+    assert_eq!(atlas.height(), 32);
+    assert_eq!(rects.len(), 3);
+    for (i, a) in rects.iter().enumerate() {
+        for b in &rects[i + 1..] {
+            let overlaps = a.origin.x < b.origin.x + b.size.width &&
+                b.origin.x < a.origin.x + a.size.width &&
+                a.origin.y < b.origin.y + b.size.height &&
+                b.origin.y < a.origin.y + a.size.height;
+            assert!(!overlaps);
+        }
+    }
+}
+
+#[test]
+fn flipped_vertically_test() {
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None, 4, 4, 8, 0, &cs,
+                                               kCGImageAlphaPremultipliedLast);
+    // Paint the top row red.
+    ctx.set_rgb_fill_color(1., 0., 0., 1.);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0., 3.), &CGSize::new(4., 1.)));
+    let image = ctx.create_image().unwrap();
+    assert_eq!(&image.data()[..4], &[255, 0, 0, 255]);
+
+    let flipped = image.flipped_vertically().unwrap();
+    let data = flipped.data();
+    let last_row = 3 * flipped.bytes_per_row();
+    assert_eq!(&data[..4], &[0, 0, 0, 0]);
+    assert_eq!(&data[last_row..last_row + 4], &[255, 0, 0, 255]);
+}
+
+#[test]
+fn to_straight_alpha_test() {
+    use base::kCGBitmapAlphaInfoMask;
+
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None, 2, 2, 8, 0, &cs,
+                                               kCGImageAlphaPremultipliedLast);
+    ctx.set_rgb_fill_color(1., 0., 0., 0.5);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(2., 2.)));
+    let image = ctx.create_image().unwrap();
+
+    let straight = image.to_straight_alpha().unwrap();
+    assert_eq!(straight.bitmap_info() & kCGBitmapAlphaInfoMask, kCGImageAlphaLast);
+    let data = straight.data();
+    assert_eq!(data[0], 255);
+    assert_eq!(data[1], 0);
+    assert_eq!(data[3], image.data()[3]);
+}
+
+#[test]
+#[cfg(target_os = "macos")]
+fn fit_to_display_test() {
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None, 40, 30, 8, 0, &cs,
+                                               kCGImageAlphaPremultipliedLast);
+    let image = ctx.create_image().unwrap();
+
+    let display = CGDisplay::main();
+    let mode = display.display_mode().unwrap();
+    for &resize in &[ResizeMode::Stretch, ResizeMode::AspectFit, ResizeMode::AspectFill] {
+        let wallpaper = image.fit_to_display(&display, resize).unwrap();
+        assert_eq!(wallpaper.width() as u64, mode.pixel_width());
+        assert_eq!(wallpaper.height() as u64, mode.pixel_height());
+    }
+}
+
+#[test]
+fn dominant_colors_test() {
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None, 100, 50, 8, 0, &cs,
+                                               kCGImageAlphaPremultipliedLast);
+    ctx.set_rgb_fill_color(1., 0., 0., 1.);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(50., 50.)));
+    ctx.set_rgb_fill_color(0., 0., 1., 1.);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(50., 0.), &CGSize::new(50., 50.)));
+    let image = ctx.create_image().unwrap();
+
+    let mut colors = image.dominant_colors(2);
+    colors.sort();
+    assert_eq!(colors.len(), 2);
+    let near = |a: (u8, u8, u8), b: (u8, u8, u8)| {
+        (a.0 as i32 - b.0 as i32).abs() < 16 &&
+            (a.1 as i32 - b.1 as i32).abs() < 16 &&
+            (a.2 as i32 - b.2 as i32).abs() < 16
+    };
+    assert!(near(colors[0], (0, 0, 255)));
+    assert!(near(colors[1], (255, 0, 0)));
+}
+
+#[test]
+fn rotated_test() {
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None, 8, 4, 8, 0, &cs,
+                                               kCGImageAlphaPremultipliedLast);
+    // Paint the top-left pixel red.
+    ctx.set_rgb_fill_color(1., 0., 0., 1.);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0., 3.), &CGSize::new(1., 1.)));
+    let image = ctx.create_image().unwrap();
+
+    let rotated = image.rotated(1).unwrap();
+    assert_eq!(rotated.width(), 4);
+    assert_eq!(rotated.height(), 8);
+    // A clockwise quarter turn moves the top-left corner to the top-right.
+    let data = rotated.data();
+    assert_eq!(&data[12..16], &[255, 0, 0, 255]);
+
+    assert_eq!(image.rotated(2).unwrap().width(), 8);
+    assert!(image.rotated(4).is_none());
+}
+
+#[test]
+fn extract_channel_test() {
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None, 4, 4, 8, 0, &cs,
+                                               kCGImageAlphaPremultipliedLast);
+    ctx.set_rgb_fill_color(1., 0., 0., 1.);
+    ctx.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(4., 4.)));
+    let image = ctx.create_image().unwrap();
+
+    let alpha = image.extract_channel(Channel::Alpha).unwrap();
+    assert_eq!(alpha.width(), 4);
+    assert_eq!(alpha.bits_per_pixel(), 8);
+    assert!(alpha.data().bytes().iter().all(|&value| value == 255));
+    let green = image.extract_channel(Channel::Green).unwrap();
+    assert!(green.data().bytes().iter().all(|&value| value == 0));
+}
+
+#[test]
+fn subsampled_test() {
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None, 8, 6, 8, 0, &cs,
+                                               kCGImageAlphaPremultipliedLast);
+    let image = ctx.create_image().unwrap();
+    let preview = image.subsampled(2).unwrap();
+    assert_eq!(preview.width(), 4);
+    assert_eq!(preview.height(), 3);
+    assert!(image.subsampled(0).is_none());
+}
+
 #[link(name = "CoreGraphics", kind = "framework")]
 extern {
     fn CGImageGetTypeID() -> CFTypeID;
+    fn CGImageCreate(width: size_t,
+                     height: size_t,
+                     bits_per_component: size_t,
+                     bits_per_pixel: size_t,
+                     bytes_per_row: size_t,
+                     space: ::sys::CGColorSpaceRef,
+                     bitmap_info: u32,
+                     provider: ::sys::CGDataProviderRef,
+                     decode: *const CGFloat,
+                     should_interpolate: bool,
+                     intent: CGColorRenderingIntent)
+                     -> ::sys::CGImageRef;
     fn CGImageGetWidth(image: ::sys::CGImageRef) -> size_t;
     fn CGImageGetHeight(image: ::sys::CGImageRef) -> size_t;
     fn CGImageGetBitsPerComponent(image: ::sys::CGImageRef) -> size_t;
     fn CGImageGetBitsPerPixel(image: ::sys::CGImageRef) -> size_t;
     fn CGImageGetBytesPerRow(image: ::sys::CGImageRef) -> size_t;
+    fn CGImageGetBitmapInfo(image: ::sys::CGImageRef) -> u32;
+    fn CGImageGetAlphaInfo(image: ::sys::CGImageRef) -> u32;
     fn CGImageGetColorSpace(image: ::sys::CGImageRef) -> ::sys::CGColorSpaceRef;
+    fn CGColorSpaceGetNumberOfComponents(space: ::sys::CGColorSpaceRef) -> size_t;
     fn CGImageGetDataProvider(image: ::sys::CGImageRef) -> ::sys::CGDataProviderRef;
     fn CGImageRelease(image: ::sys::CGImageRef);
+    fn CGImageCreateWithImageInRect(image: ::sys::CGImageRef, rect: CGRect) -> ::sys::CGImageRef;
 
-    //fn CGImageGetAlphaInfo(image: ::sys::CGImageRef) -> CGImageAlphaInfo;
-    //fn CGImageCreateCopyWithColorSpace(image: ::sys::CGImageRef, space: ::sys::CGColorSpaceRef) -> ::sys::CGImageRef
+    fn CGImageMaskCreate(width: size_t,
+                         height: size_t,
+                         bitsPerComponent: size_t,
+                         bitsPerPixel: size_t,
+                         bytesPerRow: size_t,
+                         provider: ::sys::CGDataProviderRef,
+                         decode: *const CGFloat,
+                         shouldInterpolate: bool)
+                         -> ::sys::CGImageRef;
+    fn CGImageCreateWithMask(image: ::sys::CGImageRef,
+                             mask: ::sys::CGImageRef)
+                             -> ::sys::CGImageRef;
+    fn CGImageCreateWithMaskingColors(image: ::sys::CGImageRef,
+                                      components: *const CGFloat)
+                                      -> ::sys::CGImageRef;
+    fn CGImageCreateCopyWithColorSpace(image: ::sys::CGImageRef,
+                                       space: ::sys::CGColorSpaceRef)
+                                       -> ::sys::CGImageRef;
 }
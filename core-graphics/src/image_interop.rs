@@ -0,0 +1,88 @@
+// Copyright 2018 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conversions between `CGImage` and the `image` crate's images, enabled by the
+//! `image-interop` feature.
+
+use base::{CGFloat, kCGImageAlphaPremultipliedLast};
+use color_space::CGColorSpace;
+use context::CGContext;
+use geometry::{CGPoint, CGRect, CGSize};
+use image::CGImage;
+use image_crate::{DynamicImage, RgbaImage};
+use std::cmp;
+use std::convert::TryFrom;
+
+/// Converts any image to sRGB pixels with straight alpha. Fails for empty images.
+impl<'a> TryFrom<&'a CGImage> for DynamicImage {
+    type Error = ();
+
+    fn try_from(image: &'a CGImage) -> Result<DynamicImage, ()> {
+        let (width, height) = (image.width(), image.height());
+        if width == 0 || height == 0 {
+            return Err(());
+        }
+        // Drawing the image converts it from whatever layout and color space it has.
+        let mut context = CGContext::create_bitmap_context(None, width, height, 8, 0,
+                                                           &CGColorSpace::create_srgb(),
+                                                           kCGImageAlphaPremultipliedLast);
+        let rect = CGRect::new(&CGPoint::new(0., 0.),
+                               &CGSize::new(width as CGFloat, height as CGFloat));
+        context.draw_image(rect, image);
+
+        let bytes_per_row = context.bytes_per_row();
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for row in context.data().chunks(bytes_per_row) {
+            // Rows may be padded past the last pixel.
+            for pixel in row[..width * 4].chunks(4) {
+                let alpha = pixel[3] as u32;
+                if alpha == 0 || alpha == 255 {
+                    pixels.extend_from_slice(pixel);
+                    continue;
+                }
+                for &component in &pixel[..3] {
+                    pixels.push(cmp::min(255, (component as u32 * 255 + alpha / 2) / alpha) as u8);
+                }
+                pixels.push(pixel[3]);
+            }
+        }
+        RgbaImage::from_raw(width as u32, height as u32, pixels)
+            .map(DynamicImage::ImageRgba8)
+            .ok_or(())
+    }
+}
+
+/// Creates an sRGB image from the pixels, which are copied. Fails for empty images.
+impl<'a> TryFrom<&'a RgbaImage> for CGImage {
+    type Error = ();
+
+    fn try_from(image: &'a RgbaImage) -> Result<CGImage, ()> {
+        if image.width() == 0 || image.height() == 0 {
+            return Err(());
+        }
+        Ok(CGImage::from_rgba8(image, image.width() as usize, image.height() as usize))
+    }
+}
+
+#[test]
+fn round_trip_test() {
+    let mut rgba = RgbaImage::new(3, 2);
+    rgba.put_pixel(0, 0, ::image_crate::Rgba([255, 0, 0, 255]));
+    rgba.put_pixel(2, 1, ::image_crate::Rgba([0, 0, 255, 128]));
+
+    let image = CGImage::try_from(&rgba).unwrap();
+    assert_eq!((image.width(), image.height()), (3, 2));
+    let back = DynamicImage::try_from(&image).unwrap().to_rgba8();
+    assert_eq!(back.get_pixel(0, 0).0, [255, 0, 0, 255]);
+    let [red, _, blue, alpha] = back.get_pixel(2, 1).0;
+    assert_eq!((red, alpha), (0, 128));
+    assert!(blue >= 253);
+
+    assert!(CGImage::try_from(&RgbaImage::new(0, 0)).is_err());
+}
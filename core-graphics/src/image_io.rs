@@ -0,0 +1,459 @@
+// Copyright 2018 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use core_foundation::array::CFArray;
+use core_foundation::base::{CFRelease, CFRetain, CFType, CFTypeID, TCFType};
+use core_foundation::data::{CFData, CFDataRef};
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::number::CFNumber;
+use core_foundation::string::{CFString, CFStringRef};
+use core_foundation::url::{CFURL, CFURLRef};
+use data_consumer::CGDataConsumer;
+use image::CGImage;
+use libc::size_t;
+use std::ptr;
+
+use foreign_types::ForeignType;
+
+/// Commonly used camera settings from the EXIF metadata of an image.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExifInfo {
+    /// The exposure time, in seconds.
+    pub exposure_time: Option<f64>,
+    /// The ISO speed rating.
+    pub iso: Option<i64>,
+    /// The date and time the image was captured, as written by the camera
+    /// (`YYYY:MM:DD HH:MM:SS`).
+    pub date_time_original: Option<String>,
+}
+
+/// A file format images can be encoded to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Tiff,
+    /// HEIF with HEVC compression. Encoding it requires macOS 10.13.4 and suitable hardware.
+    Heic,
+}
+
+impl ImageFormat {
+    /// Returns the uniform type identifier of the format, e.g. `public.png`.
+    pub fn type_identifier(&self) -> CFString {
+        CFString::from_static_string(match *self {
+            ImageFormat::Png => "public.png",
+            ImageFormat::Jpeg => "public.jpeg",
+            ImageFormat::Tiff => "public.tiff",
+            ImageFormat::Heic => "public.heic",
+        })
+    }
+
+    /// Returns the format with the uniform type identifier `type_`, if it is one of these.
+    pub fn from_type_identifier(type_: &CFString) -> Option<ImageFormat> {
+        [ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::Tiff, ImageFormat::Heic].iter()
+            .find(|format| format.type_identifier() == *type_)
+            .cloned()
+    }
+}
+
+foreign_type! {
+    #[doc(hidden)]
+    type CType = ::sys::CGImageSource;
+    fn drop = |p| CFRelease(p as *mut _);
+    fn clone = |p| CFRetain(p as *const _) as *mut _;
+    pub struct CGImageSource;
+    pub struct CGImageSourceRef;
+}
+
+foreign_type! {
+    #[doc(hidden)]
+    type CType = ::sys::CGImageDestination;
+    fn drop = |p| CFRelease(p as *mut _);
+    fn clone = |p| CFRetain(p as *const _) as *mut _;
+    pub struct CGImageDestination;
+    pub struct CGImageDestinationRef;
+}
+
+impl CGImageSource {
+    pub fn type_id() -> CFTypeID {
+        unsafe {
+            CGImageSourceGetTypeID()
+        }
+    }
+
+    /// Creates an image source that reads from the encoded image in `data`.
+    pub fn from_data(data: &CFData) -> Option<CGImageSource> {
+        unsafe {
+            let source = CGImageSourceCreateWithData(data.as_concrete_TypeRef(), ptr::null());
+            if !source.is_null() {
+                Some(CGImageSource::from_ptr(source))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Creates an image source that reads from the encoded image at `url`.
+    pub fn from_url(url: &CFURL) -> Option<CGImageSource> {
+        unsafe {
+            let source = CGImageSourceCreateWithURL(url.as_concrete_TypeRef(), ptr::null());
+            if !source.is_null() {
+                Some(CGImageSource::from_ptr(source))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the uniform type identifier of the source's format, e.g. `public.png`, or
+    /// `None` if it isn't known (yet).
+    pub fn type_identifier(&self) -> Option<CFString> {
+        unsafe {
+            let type_ = CGImageSourceGetType(self.as_ptr());
+            if !type_.is_null() {
+                Some(CFString::wrap_under_get_rule(type_))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the number of images (not including the thumbnail) in the source.
+    pub fn count(&self) -> usize {
+        unsafe {
+            CGImageSourceGetCount(self.as_ptr())
+        }
+    }
+
+    /// Decodes the image at `index`.
+    pub fn image_at_index(&self, index: usize) -> Option<CGImage> {
+        unsafe {
+            let image = CGImageSourceCreateImageAtIndex(self.as_ptr(), index, ptr::null());
+            if !image.is_null() {
+                Some(CGImage::from_ptr(image))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the properties of the image at `index`, keyed by the `kCGImageProperty*`
+    /// constants.
+    pub fn properties_at_index(&self, index: usize) -> Option<CFDictionary<CFString, CFType>> {
+        unsafe {
+            let properties = CGImageSourceCopyPropertiesAtIndex(self.as_ptr(),
+                                                                index,
+                                                                ptr::null());
+            if !properties.is_null() {
+                Some(TCFType::wrap_under_create_rule(properties))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the EXIF metadata of the image at `index`, keyed by the
+    /// `kCGImagePropertyExif*` constants, or `None` if the image has none.
+    pub fn exif_metadata(&self, index: usize) -> Option<CFDictionary<CFString, CFType>> {
+        self.properties_at_index(index).and_then(|properties| unsafe {
+            find_dictionary(&properties, kCGImagePropertyExifDictionary)
+        })
+    }
+
+    /// Returns the exposure, ISO and capture date from the EXIF metadata of the
+    /// image at `index`, or `None` if the image has no EXIF metadata.
+    pub fn exif_info(&self, index: usize) -> Option<ExifInfo> {
+        self.exif_metadata(index).map(|exif| unsafe {
+            let iso = exif.find(kCGImagePropertyExifISOSpeedRatings)
+                .and_then(|value| value.clone().downcast_into::<CFArray<CFType>>())
+                .and_then(|ratings| {
+                    ratings.get(0).and_then(|rating| rating.downcast::<CFNumber>())
+                })
+                .and_then(|rating| rating.to_i64());
+            ExifInfo {
+                exposure_time: find_number(&exif, kCGImagePropertyExifExposureTime),
+                iso: iso,
+                date_time_original: exif.find(kCGImagePropertyExifDateTimeOriginal)
+                    .and_then(|value| value.downcast::<CFString>())
+                    .map(|date| date.to_string()),
+            }
+        })
+    }
+
+    /// Returns the delay time, in seconds, of each frame of an animated GIF or PNG.
+    ///
+    /// The unclamped delay is preferred over the (browser-compatible) clamped one when both are
+    /// present; frames without any delay information report 0. Single-frame images return an
+    /// empty `Vec`.
+    pub fn frame_delays(&self) -> Vec<f64> {
+        let count = self.count();
+        if count < 2 {
+            return Vec::new();
+        }
+        (0..count).map(|index| {
+            self.properties_at_index(index).and_then(|properties| unsafe {
+                find_dictionary(&properties, kCGImagePropertyGIFDictionary)
+                    .and_then(|gif| {
+                        find_number(&gif, kCGImagePropertyGIFUnclampedDelayTime)
+                            .or_else(|| find_number(&gif, kCGImagePropertyGIFDelayTime))
+                    })
+                    .or_else(|| {
+                        find_dictionary(&properties, kCGImagePropertyPNGDictionary)
+                            .and_then(|png| {
+                                find_number(&png, kCGImagePropertyAPNGUnclampedDelayTime)
+                                    .or_else(|| find_number(&png, kCGImagePropertyAPNGDelayTime))
+                            })
+                    })
+            }).unwrap_or(0.)
+        }).collect()
+    }
+}
+
+impl CGImageDestination {
+    pub fn type_id() -> CFTypeID {
+        unsafe {
+            CGImageDestinationGetTypeID()
+        }
+    }
+
+    /// Creates an image destination that writes `count` images of the file type `type_`, a
+    /// uniform type identifier such as `public.png`, to `consumer`.
+    pub fn from_consumer(consumer: &CGDataConsumer, type_: &CFString, count: usize)
+                         -> Option<CGImageDestination> {
+        unsafe {
+            let destination = CGImageDestinationCreateWithDataConsumer(
+                consumer.as_ptr(),
+                type_.as_concrete_TypeRef(),
+                count,
+                ptr::null());
+            if !destination.is_null() {
+                Some(CGImageDestination::from_ptr(destination))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Creates an image destination that writes `count` images of the file type `type_` to the
+    /// file at `url`, replacing it if it exists.
+    pub fn from_url(url: &CFURL, type_: &CFString, count: usize)
+                    -> Option<CGImageDestination> {
+        unsafe {
+            let destination = CGImageDestinationCreateWithURL(url.as_concrete_TypeRef(),
+                                                              type_.as_concrete_TypeRef(),
+                                                              count,
+                                                              ptr::null());
+            if !destination.is_null() {
+                Some(CGImageDestination::from_ptr(destination))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Adds an image to the destination.
+    ///
+    /// `properties` is keyed by the `kCGImageProperty*` constants, e.g.
+    /// `kCGImagePropertyDPIWidth`/`kCGImagePropertyDPIHeight` to set the resolution of the
+    /// image or `kCGImagePropertyOrientation` to set its orientation.
+    pub fn add_image(&self, image: &CGImage, properties: Option<&CFDictionary<CFString, CFType>>) {
+        unsafe {
+            let properties = properties.map_or(ptr::null(), |p| p.as_concrete_TypeRef());
+            CGImageDestinationAddImage(self.as_ptr(), image.as_ptr(), properties)
+        }
+    }
+
+    /// Writes the image data and properties to the destination. Returns `false` if the images
+    /// could not be written.
+    pub fn finalize(&self) -> bool {
+        unsafe {
+            CGImageDestinationFinalize(self.as_ptr())
+        }
+    }
+}
+
+fn find_dictionary(dict: &CFDictionary<CFString, CFType>, key: CFStringRef)
+                   -> Option<CFDictionary<CFString, CFType>> {
+    dict.find(key).and_then(|value| value.clone().downcast_into())
+}
+
+fn find_number(dict: &CFDictionary<CFString, CFType>, key: CFStringRef) -> Option<f64> {
+    dict.find(key)
+        .and_then(|value| value.downcast::<CFNumber>())
+        .and_then(|number| number.to_f64())
+}
+
+#[test]
+fn from_url_test() {
+    use context::CGContext;
+
+    let path = ::std::env::temp_dir().join("core-graphics-image-source-from-url-test.tiff");
+    let url = CFURL::from_path(&path, false).unwrap();
+    let image = CGContext::create_argb_bitmap(3, 2).create_image().unwrap();
+    assert!(image.write_to_url(&url, ImageFormat::Tiff));
+
+    let source = CGImageSource::from_url(&url).unwrap();
+    let type_ = source.type_identifier().unwrap();
+    assert_eq!(ImageFormat::from_type_identifier(&type_), Some(ImageFormat::Tiff));
+    let decoded = source.image_at_index(0).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (3, 2));
+    let _ = ::std::fs::remove_file(path);
+}
+
+#[test]
+fn frame_delays_test() {
+    // A 1x1 GIF with two frames, each shown for 10/100ths of a second.
+    const GIF: &[u8] = &[
+        0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x2c,
+        0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00,
+        0x21, 0xf9, 0x04, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00, 0x01,
+        0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+    ];
+
+    let source = CGImageSource::from_data(&CFData::from_buffer(GIF)).unwrap();
+    assert_eq!(source.count(), 2);
+    let delays = source.frame_delays();
+    assert_eq!(delays.len(), 2);
+    assert!(delays.iter().all(|&delay| delay > 0.));
+}
+
+#[test]
+fn add_image_properties_test() {
+    use base::kCGImageAlphaPremultipliedLast;
+    use color_space::CGColorSpace;
+    use context::CGContext;
+    use std::sync::{Arc, Mutex};
+
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None, 4, 4, 8, 0, &cs,
+                                               kCGImageAlphaPremultipliedLast);
+    let image = ctx.create_image().unwrap();
+
+    let buffer = Arc::new(Mutex::new(vec![]));
+    {
+        let consumer = CGDataConsumer::from_buffer(buffer.clone());
+        let png = CFString::from_static_string("public.png");
+        let destination = CGImageDestination::from_consumer(&consumer, &png, 1).unwrap();
+        let dpi = CFNumber::from(144.0f64).as_CFType();
+        let properties = unsafe {
+            CFDictionary::from_CFType_pairs(&[
+                (CFString::wrap_under_get_rule(kCGImagePropertyDPIWidth), dpi.clone()),
+                (CFString::wrap_under_get_rule(kCGImagePropertyDPIHeight), dpi),
+            ])
+        };
+        destination.add_image(&image, Some(&properties));
+        assert!(destination.finalize());
+    }
+
+    let data = CFData::from_buffer(&buffer.lock().unwrap());
+    let source = CGImageSource::from_data(&data).unwrap();
+    let properties = source.properties_at_index(0).unwrap();
+    // PNG stores the resolution as whole pixels per meter, so 144 DPI doesn't
+    // round trip exactly.
+    for &key in unsafe { &[kCGImagePropertyDPIWidth, kCGImagePropertyDPIHeight] } {
+        let dpi = find_number(&properties, key).unwrap();
+        assert!((dpi - 144.).abs() < 0.1, "{}", dpi);
+    }
+}
+
+#[test]
+fn exif_metadata_test() {
+    use base::kCGImageAlphaNoneSkipLast;
+    use color_space::CGColorSpace;
+    use context::CGContext;
+    use std::sync::{Arc, Mutex};
+
+    let cs = CGColorSpace::create_device_rgb();
+    let ctx = CGContext::create_bitmap_context(None, 8, 8, 8, 0, &cs,
+                                               kCGImageAlphaNoneSkipLast);
+    let image = ctx.create_image().unwrap();
+
+    // Write a JPEG carrying EXIF metadata, as a camera would.
+    let buffer = Arc::new(Mutex::new(vec![]));
+    {
+        let consumer = CGDataConsumer::from_buffer(buffer.clone());
+        let jpeg = CFString::from_static_string("public.jpeg");
+        let destination = CGImageDestination::from_consumer(&consumer, &jpeg, 1).unwrap();
+        let properties = unsafe {
+            let exif = CFDictionary::from_CFType_pairs(&[
+                (CFString::wrap_under_get_rule(kCGImagePropertyExifExposureTime),
+                 CFNumber::from(0.008f64).as_CFType()),
+                (CFString::wrap_under_get_rule(kCGImagePropertyExifISOSpeedRatings),
+                 CFArray::from_CFTypes(&[CFNumber::from(200)]).as_CFType()),
+                (CFString::wrap_under_get_rule(kCGImagePropertyExifDateTimeOriginal),
+                 CFString::from_static_string("2018:06:01 12:34:56").as_CFType()),
+            ]);
+            CFDictionary::from_CFType_pairs(&[
+                (CFString::wrap_under_get_rule(kCGImagePropertyExifDictionary), exif.as_CFType()),
+            ])
+        };
+        destination.add_image(&image, Some(&properties));
+        assert!(destination.finalize());
+    }
+
+    let data = CFData::from_buffer(&buffer.lock().unwrap());
+    let source = CGImageSource::from_data(&data).unwrap();
+    assert!(source.exif_metadata(0).unwrap().len() > 0);
+    let info = source.exif_info(0).unwrap();
+    assert!((info.exposure_time.unwrap() - 0.008).abs() < 1e-6);
+    assert_eq!(info.iso, Some(200));
+    assert_eq!(info.date_time_original, Some("2018:06:01 12:34:56".to_owned()));
+}
+
+#[link(name = "ImageIO", kind = "framework")]
+extern {
+    pub static kCGImagePropertyDPIWidth: CFStringRef;
+    pub static kCGImagePropertyDPIHeight: CFStringRef;
+    pub static kCGImagePropertyOrientation: CFStringRef;
+    pub static kCGImagePropertyExifDictionary: CFStringRef;
+    pub static kCGImagePropertyExifExposureTime: CFStringRef;
+    pub static kCGImagePropertyExifISOSpeedRatings: CFStringRef;
+    pub static kCGImagePropertyExifDateTimeOriginal: CFStringRef;
+    pub static kCGImagePropertyGIFDictionary: CFStringRef;
+    pub static kCGImagePropertyGIFDelayTime: CFStringRef;
+    pub static kCGImagePropertyGIFUnclampedDelayTime: CFStringRef;
+    pub static kCGImagePropertyPNGDictionary: CFStringRef;
+    pub static kCGImagePropertyAPNGDelayTime: CFStringRef;
+    pub static kCGImagePropertyAPNGUnclampedDelayTime: CFStringRef;
+
+    fn CGImageSourceGetTypeID() -> CFTypeID;
+    fn CGImageSourceCreateWithData(data: CFDataRef,
+                                   options: CFDictionaryRef)
+                                   -> ::sys::CGImageSourceRef;
+    fn CGImageSourceCreateWithURL(url: CFURLRef,
+                                  options: CFDictionaryRef)
+                                  -> ::sys::CGImageSourceRef;
+    fn CGImageSourceGetType(isrc: ::sys::CGImageSourceRef) -> CFStringRef;
+    fn CGImageSourceGetCount(isrc: ::sys::CGImageSourceRef) -> size_t;
+    fn CGImageSourceCreateImageAtIndex(isrc: ::sys::CGImageSourceRef,
+                                       index: size_t,
+                                       options: CFDictionaryRef)
+                                       -> ::sys::CGImageRef;
+    fn CGImageSourceCopyPropertiesAtIndex(isrc: ::sys::CGImageSourceRef,
+                                          index: size_t,
+                                          options: CFDictionaryRef)
+                                          -> CFDictionaryRef;
+
+    fn CGImageDestinationGetTypeID() -> CFTypeID;
+    fn CGImageDestinationCreateWithDataConsumer(consumer: ::sys::CGDataConsumerRef,
+                                                type_: CFStringRef,
+                                                count: size_t,
+                                                options: CFDictionaryRef)
+                                                -> ::sys::CGImageDestinationRef;
+    fn CGImageDestinationCreateWithURL(url: CFURLRef,
+                                       type_: CFStringRef,
+                                       count: size_t,
+                                       options: CFDictionaryRef)
+                                       -> ::sys::CGImageDestinationRef;
+    fn CGImageDestinationAddImage(idst: ::sys::CGImageDestinationRef,
+                                  image: ::sys::CGImageRef,
+                                  properties: CFDictionaryRef);
+    fn CGImageDestinationFinalize(idst: ::sys::CGImageDestinationRef) -> bool;
+}
@@ -0,0 +1,75 @@
+// Copyright 2018 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Offscreen layers, for caching drawing that is repeated many times.
+
+use context::CGContext;
+use core_foundation::base::{CFRelease, CFRetain, CFTypeID};
+use core_foundation::dictionary::CFDictionaryRef;
+use foreign_types::ForeignType;
+use geometry::CGSize;
+use std::ptr;
+
+foreign_type! {
+    #[doc(hidden)]
+    type CType = ::sys::CGLayer;
+    fn drop = |p| CFRelease(p as *mut _);
+    fn clone = |p| CFRetain(p as *const _) as *mut _;
+    pub struct CGLayer;
+    pub struct CGLayerRef;
+}
+
+impl CGLayer {
+    pub fn type_id() -> CFTypeID {
+        unsafe {
+            CGLayerGetTypeID()
+        }
+    }
+
+    /// Creates a layer of `size`, in user space units, whose drawing is optimized for
+    /// `context`, e.g. stored on the GPU for a window context.
+    ///
+    /// Draw into the layer once through `context()`, then draw the layer into `context` as
+    /// often as needed with `CGContext::draw_layer_in_rect` or `draw_layer_at_point`.
+    pub fn new(context: &CGContext, size: CGSize) -> Option<CGLayer> {
+        unsafe {
+            let result = CGLayerCreateWithContext(context.as_ptr(), size, ptr::null());
+            if !result.is_null() {
+                Some(CGLayer::from_ptr(result))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the context drawing into the layer.
+    pub fn context(&self) -> CGContext {
+        unsafe {
+            let context = CGLayerGetContext(self.as_ptr());
+            CGContext::from_ptr(CFRetain(context as *const _) as *mut _)
+        }
+    }
+
+    pub fn size(&self) -> CGSize {
+        unsafe {
+            CGLayerGetSize(self.as_ptr())
+        }
+    }
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern {
+    fn CGLayerGetTypeID() -> CFTypeID;
+    fn CGLayerCreateWithContext(context: ::sys::CGContextRef,
+                                size: CGSize,
+                                auxiliaryInfo: CFDictionaryRef)
+                                -> ::sys::CGLayerRef;
+    fn CGLayerGetContext(layer: ::sys::CGLayerRef) -> ::sys::CGContextRef;
+    fn CGLayerGetSize(layer: ::sys::CGLayerRef) -> CGSize;
+}
@@ -14,23 +14,46 @@ extern crate core_foundation;
 #[cfg(target_os = "macos")]
 extern crate bitflags;
 
+#[cfg(target_os = "macos")]
+extern crate block;
+
 #[macro_use]
 extern crate foreign_types;
 
+#[cfg(all(target_os = "macos", feature = "async"))]
+extern crate futures;
+
+#[cfg(feature = "image-interop")]
+extern crate image as image_crate;
+
 pub mod base;
+pub mod color;
 pub mod color_space;
 pub mod context;
+pub mod data_consumer;
 pub mod data_provider;
 #[cfg(target_os = "macos")]
 pub mod display;
 #[cfg(target_os = "macos")]
+pub mod display_stream;
+#[cfg(target_os = "macos")]
 pub mod event;
 #[cfg(target_os = "macos")]
 pub mod event_source;
 pub mod font;
+#[cfg(all(target_os = "macos", feature = "async"))]
+pub mod frame_stream;
 pub mod geometry;
+pub mod gradient;
 #[cfg(target_os = "macos")]
 pub mod private;
 pub mod image;
+#[cfg(feature = "image-interop")]
+pub mod image_interop;
+pub mod image_io;
+pub mod layer;
 pub mod path;
+pub mod pattern;
+pub mod pdf_context;
+pub mod shading;
 mod sys;
@@ -8,13 +8,16 @@
 // except according to those terms.
 
 use core_foundation::base::{CFRelease, CFRetain, CFTypeID};
-use foreign_types::ForeignType;
-use geometry::CGPoint;
-use libc::c_void;
+use foreign_types::{ForeignType, ForeignTypeRef};
+use base::CGFloat;
+use geometry::{CGAffineTransform, CGPoint, CGRect};
+use libc::{c_void, size_t};
 use std::fmt::{self, Debug, Formatter};
 use std::marker::PhantomData;
 use std::ops::Deref;
+use std::ptr;
 use std::slice;
+use std::vec;
 
 foreign_type! {
     #[doc(hidden)]
@@ -43,6 +46,304 @@ impl CGPath {
             (**closure)(CGPathElementRef::new(element))
         }
     }
+
+    /// Calls `f` with each element of the path, in order.
+    pub fn for_each<F>(&self, mut f: F) where F: FnMut(PathElement) {
+        let mut f: &mut FnMut(PathElement) = &mut f;
+        unsafe {
+            CGPathApply(self.as_ptr(), &mut f as *mut _ as *mut c_void, do_for_each);
+        }
+
+        unsafe extern "C" fn do_for_each(info: *mut c_void, element: *const CGPathElement) {
+            let f = &mut *(info as *mut &mut FnMut(PathElement));
+            f((*element).to_path_element())
+        }
+    }
+
+    /// Returns the elements of the path, e.g. to convert it to another path representation.
+    pub fn elements(&self) -> vec::IntoIter<PathElement> {
+        let mut elements = vec![];
+        self.for_each(|element| elements.push(element));
+        elements.into_iter()
+    }
+
+    /// Returns the smallest rect containing all points of the path, including control points.
+    pub fn bounding_box(&self) -> CGRect {
+        unsafe {
+            CGPathGetBoundingBox(self.as_ptr())
+        }
+    }
+
+    /// Returns the smallest rect containing the path, not including control points.
+    pub fn path_bounding_box(&self) -> CGRect {
+        unsafe {
+            CGPathGetPathBoundingBox(self.as_ptr())
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        unsafe {
+            CGPathIsEmpty(self.as_ptr())
+        }
+    }
+
+    /// Returns whether `point` is inside the area the path would fill, using the even-odd
+    /// rule if `eo_fill` is set and the nonzero winding rule otherwise. `transform` is
+    /// applied to the path first.
+    pub fn contains_point(&self, point: CGPoint, eo_fill: bool,
+                          transform: Option<&CGAffineTransform>) -> bool {
+        unsafe {
+            CGPathContainsPoint(self.as_ptr(), transform_ptr(transform), point, eo_fill)
+        }
+    }
+
+    /// Returns the rect the path consists of, if it is just a rect.
+    pub fn as_rect(&self) -> Option<CGRect> {
+        let mut rect = CGRect::default();
+        if unsafe { CGPathIsRect(self.as_ptr(), &mut rect) } {
+            Some(rect)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn is_rect(&self) -> bool {
+        self.as_rect().is_some()
+    }
+
+    /// Returns the outline of the area stroking the path with the given line parameters
+    /// would paint, e.g. to fill it instead or to convert it to another representation.
+    pub fn copy_by_stroking(&self, transform: Option<&CGAffineTransform>, line_width: CGFloat,
+                            line_cap: CGLineCap, line_join: CGLineJoin, miter_limit: CGFloat)
+                            -> CGPath {
+        unsafe {
+            let result = CGPathCreateCopyByStrokingPath(self.as_ptr(),
+                                                        transform_ptr(transform),
+                                                        line_width,
+                                                        line_cap,
+                                                        line_join,
+                                                        miter_limit);
+            assert!(!result.is_null());
+            CGPath::from_ptr(result)
+        }
+    }
+
+    /// Returns the path broken into dashes: alternating `lengths` of drawn and skipped
+    /// segments, starting `phase` units into the pattern.
+    ///
+    /// Returns `None` if `lengths` is empty or otherwise not a valid dash pattern.
+    pub fn copy_by_dashing(&self, transform: Option<&CGAffineTransform>, phase: CGFloat,
+                           lengths: &[CGFloat]) -> Option<CGPath> {
+        unsafe {
+            let result = CGPathCreateCopyByDashingPath(self.as_ptr(),
+                                                       transform_ptr(transform),
+                                                       phase,
+                                                       lengths.as_ptr(),
+                                                       lengths.len());
+            if !result.is_null() {
+                Some(CGPath::from_ptr(result))
+            } else {
+                None
+            }
+        }
+    }
+
+    pub fn copy_by_transforming(&self, transform: &CGAffineTransform) -> CGPath {
+        unsafe {
+            let result = CGPathCreateCopyByTransformingPath(self.as_ptr(), transform);
+            assert!(!result.is_null());
+            CGPath::from_ptr(result)
+        }
+    }
+
+    /// Returns a mutable copy of the path, to add to it.
+    pub fn mutable_copy(&self) -> CGMutablePath {
+        unsafe {
+            let result = CGPathCreateMutableCopy(self.as_ptr());
+            assert!(!result.is_null());
+            CGMutablePath::from_ptr(result)
+        }
+    }
+}
+
+impl PartialEq for CGPathRef {
+    fn eq(&self, other: &CGPathRef) -> bool {
+        unsafe {
+            CGPathEqualToPath(self.as_ptr(), other.as_ptr())
+        }
+    }
+}
+
+impl PartialEq for CGPath {
+    fn eq(&self, other: &CGPath) -> bool {
+        **self == **other
+    }
+}
+
+foreign_type! {
+    #[doc(hidden)]
+    type CType = ::sys::CGPath;
+    fn drop = |p| CFRelease(p as *mut _);
+    fn clone = |p| CFRetain(p as *const _) as *mut _;
+    /// A path that can be added to, to build a `CGPath`.
+    ///
+    /// Every method optionally takes a transform that is applied to the added points.
+    pub struct CGMutablePath;
+    pub struct CGMutablePathRef;
+}
+
+impl CGMutablePath {
+    pub fn new() -> CGMutablePath {
+        unsafe {
+            let result = CGPathCreateMutable();
+            assert!(!result.is_null());
+            CGMutablePath::from_ptr(result)
+        }
+    }
+
+    /// Returns an immutable copy of the path as it is now.
+    pub fn copy(&self) -> CGPath {
+        unsafe {
+            let result = CGPathCreateCopy(self.as_ptr());
+            assert!(!result.is_null());
+            CGPath::from_ptr(result)
+        }
+    }
+
+    /// Turns the path into an immutable `CGPath`. The path is copied, as clones of this one
+    /// could otherwise still change it.
+    pub fn into_path(self) -> CGPath {
+        self.copy()
+    }
+
+    /// Returns the end point of the path, or `CGPointZero` if it is empty.
+    pub fn current_point(&self) -> CGPoint {
+        unsafe {
+            CGPathGetCurrentPoint(self.as_ptr())
+        }
+    }
+
+    /// Starts a new subpath at `(x, y)`.
+    pub fn move_to(&self, transform: Option<&CGAffineTransform>, x: CGFloat, y: CGFloat) {
+        unsafe {
+            CGPathMoveToPoint(self.as_ptr(), transform_ptr(transform), x, y)
+        }
+    }
+
+    pub fn add_line_to(&self, transform: Option<&CGAffineTransform>, x: CGFloat, y: CGFloat) {
+        unsafe {
+            CGPathAddLineToPoint(self.as_ptr(), transform_ptr(transform), x, y)
+        }
+    }
+
+    /// Adds a cubic Bézier curve from the current point to `(x, y)`, with the control points
+    /// `(cp1x, cp1y)` and `(cp2x, cp2y)`.
+    pub fn add_curve_to(&self, transform: Option<&CGAffineTransform>,
+                        cp1x: CGFloat, cp1y: CGFloat, cp2x: CGFloat, cp2y: CGFloat,
+                        x: CGFloat, y: CGFloat) {
+        unsafe {
+            CGPathAddCurveToPoint(self.as_ptr(), transform_ptr(transform),
+                                  cp1x, cp1y, cp2x, cp2y, x, y)
+        }
+    }
+
+    /// Adds a quadratic Bézier curve from the current point to `(x, y)`, with the control
+    /// point `(cpx, cpy)`.
+    pub fn add_quad_curve_to(&self, transform: Option<&CGAffineTransform>,
+                             cpx: CGFloat, cpy: CGFloat, x: CGFloat, y: CGFloat) {
+        unsafe {
+            CGPathAddQuadCurveToPoint(self.as_ptr(), transform_ptr(transform), cpx, cpy, x, y)
+        }
+    }
+
+    /// Adds an arc of the circle around `(x, y)` from `start_angle` to `end_angle`, in
+    /// radians, connected to the current point by a line if there is one.
+    pub fn add_arc(&self, transform: Option<&CGAffineTransform>,
+                   x: CGFloat, y: CGFloat, radius: CGFloat,
+                   start_angle: CGFloat, end_angle: CGFloat, clockwise: bool) {
+        unsafe {
+            CGPathAddArc(self.as_ptr(), transform_ptr(transform),
+                         x, y, radius, start_angle, end_angle, clockwise)
+        }
+    }
+
+    /// Adds `rect` as a closed subpath.
+    pub fn add_rect(&self, transform: Option<&CGAffineTransform>, rect: CGRect) {
+        unsafe {
+            CGPathAddRect(self.as_ptr(), transform_ptr(transform), rect)
+        }
+    }
+
+    /// Adds the ellipse inscribed in `rect` as a closed subpath.
+    pub fn add_ellipse(&self, transform: Option<&CGAffineTransform>, rect: CGRect) {
+        unsafe {
+            CGPathAddEllipseInRect(self.as_ptr(), transform_ptr(transform), rect)
+        }
+    }
+
+    /// Adds `rect` with its corners rounded to quarter ellipses as a closed subpath.
+    ///
+    /// The corner sizes are clamped to between 0 and half the width and height of `rect`.
+    pub fn add_rounded_rect(&self, transform: Option<&CGAffineTransform>, rect: CGRect,
+                            corner_width: CGFloat, corner_height: CGFloat) {
+        // CG aborts on corners that don't fit.
+        let corner_width = corner_width.max(0.).min(rect.size.width.abs() / 2.);
+        let corner_height = corner_height.max(0.).min(rect.size.height.abs() / 2.);
+        unsafe {
+            CGPathAddRoundedRect(self.as_ptr(), transform_ptr(transform), rect,
+                                 corner_width, corner_height)
+        }
+    }
+
+    /// Appends the subpaths of `path`.
+    pub fn add_path(&self, transform: Option<&CGAffineTransform>, path: &CGPath) {
+        unsafe {
+            CGPathAddPath(self.as_ptr(), transform_ptr(transform), path.as_ptr())
+        }
+    }
+
+    /// Closes the current subpath with a line back to its start.
+    pub fn close_subpath(&self) {
+        unsafe {
+            CGPathCloseSubpath(self.as_ptr())
+        }
+    }
+}
+
+fn transform_ptr(transform: Option<&CGAffineTransform>) -> *const CGAffineTransform {
+    transform.map_or(ptr::null(), |transform| transform as *const _)
+}
+
+/// How the ends of stroked lines are drawn.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CGLineCap {
+    Butt = 0,
+    Round = 1,
+    Square = 2,
+}
+
+/// How the joins between stroked line segments are drawn.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CGLineJoin {
+    Miter = 0,
+    Round = 1,
+    Bevel = 2,
+}
+
+/// An element of a path, with its end point last.
+#[derive(Clone, Copy, Debug)]
+pub enum PathElement {
+    MoveTo(CGPoint),
+    LineTo(CGPoint),
+    /// A quadratic Bézier curve with a control point.
+    QuadCurveTo(CGPoint, CGPoint),
+    /// A cubic Bézier curve with two control points.
+    CurveTo(CGPoint, CGPoint, CGPoint),
+    /// A line back to the start of the subpath, closing it.
+    Close,
 }
 
 #[repr(i32)]
@@ -91,6 +392,21 @@ impl Debug for CGPathElement {
 }
 
 impl CGPathElement {
+    pub fn to_path_element(&self) -> PathElement {
+        let points = self.points();
+        match self.element_type {
+            CGPathElementType::MoveToPoint => PathElement::MoveTo(points[0]),
+            CGPathElementType::AddLineToPoint => PathElement::LineTo(points[0]),
+            CGPathElementType::AddQuadCurveToPoint => {
+                PathElement::QuadCurveTo(points[0], points[1])
+            }
+            CGPathElementType::AddCurveToPoint => {
+                PathElement::CurveTo(points[0], points[1], points[2])
+            }
+            CGPathElementType::CloseSubpath => PathElement::Close,
+        }
+    }
+
     pub fn points(&self) -> &[CGPoint] {
         unsafe {
             match self.element_type {
@@ -108,8 +424,183 @@ impl CGPathElement {
 type CGPathApplierFunction = unsafe extern "C" fn(info: *mut c_void,
                                                   element: *const CGPathElement);
 
+#[test]
+fn mutable_path_test() {
+    use geometry::CGSize;
+
+    let path = CGMutablePath::new();
+    path.move_to(None, 0., 0.);
+    path.add_line_to(None, 10., 0.);
+    path.add_quad_curve_to(None, 10., 5., 5., 5.);
+    path.add_curve_to(None, 4., 5., 1., 5., 0., 10.);
+    path.close_subpath();
+    assert_eq!(path.current_point().x, 0.);
+
+    let scale = CGAffineTransform::new(2., 0., 0., 2., 0., 0.);
+    path.add_rect(Some(&scale), CGRect::new(&CGPoint::new(10., 10.), &CGSize::new(5., 5.)));
+    let path = path.into_path();
+    assert!(!path.is_empty());
+    let bounds = path.bounding_box();
+    assert_eq!((bounds.origin.x, bounds.origin.y), (0., 0.));
+    assert_eq!((bounds.size.width, bounds.size.height), (30., 30.));
+
+    let copy = path.mutable_copy();
+    copy.add_ellipse(None, CGRect::new(&CGPoint::new(-10., -10.), &CGSize::new(5., 5.)));
+    assert_eq!(copy.copy().bounding_box().origin.x, -10.);
+    assert_eq!(path.bounding_box().origin.x, 0.);
+}
+
+#[test]
+fn add_rounded_rect_test() {
+    use geometry::CGSize;
+
+    let rect = CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(10., 4.));
+    let path = CGMutablePath::new();
+    path.add_rounded_rect(None, rect, 8., 8.);
+    path.add_rounded_rect(None, rect, -1., -1.);
+    let bounds = path.into_path().bounding_box();
+    assert_eq!((bounds.size.width, bounds.size.height), (10., 4.));
+}
+
+#[test]
+fn into_path_test() {
+    let path = CGMutablePath::new();
+    path.move_to(None, 0., 0.);
+    path.add_line_to(None, 1., 1.);
+    let clone = path.clone();
+    let frozen = path.into_path();
+    clone.add_line_to(None, 5., 5.);
+    assert_eq!(frozen.bounding_box().size.width, 1.);
+}
+
+#[test]
+fn path_queries_test() {
+    use geometry::CGSize;
+
+    let rect = CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(4., 4.));
+    let path = CGMutablePath::new();
+    path.add_rect(None, rect);
+    let path = path.into_path();
+    assert!(path.is_rect());
+    assert_eq!(path.as_rect().unwrap().size.width, 4.);
+    assert!(path.contains_point(CGPoint::new(2., 2.), false, None));
+    assert!(!path.contains_point(CGPoint::new(5., 2.), false, None));
+    let translate = CGAffineTransform::new(1., 0., 0., 1., 2., 0.);
+    assert!(path.contains_point(CGPoint::new(5., 2.), false, Some(&translate)));
+
+    let holed = path.mutable_copy();
+    holed.add_rect(None, CGRect::new(&CGPoint::new(1., 1.), &CGSize::new(2., 2.)));
+    let holed = holed.into_path();
+    assert!(!holed.is_rect());
+    assert!(!holed.contains_point(CGPoint::new(2., 2.), true, None));
+    assert!(holed.contains_point(CGPoint::new(0.5, 0.5), true, None));
+
+    assert!(path == path.mutable_copy().copy());
+    assert!(path != holed);
+}
+
+#[test]
+fn derived_paths_test() {
+    let line = CGMutablePath::new();
+    line.move_to(None, 0., 0.);
+    line.add_line_to(None, 10., 0.);
+    let line = line.into_path();
+
+    let stroked = line.copy_by_stroking(None, 2., CGLineCap::Butt, CGLineJoin::Miter, 10.);
+    let bounds = stroked.path_bounding_box();
+    assert_eq!((bounds.origin.y, bounds.size.width, bounds.size.height), (-1., 10., 2.));
+    assert!(stroked.contains_point(CGPoint::new(5., 0.5), false, None));
+
+    let dashed = line.copy_by_dashing(None, 0., &[2., 3.]).unwrap();
+    let move_count = dashed.elements().filter(|element| match *element {
+        PathElement::MoveTo(_) => true,
+        _ => false,
+    }).count();
+    assert_eq!(move_count, 2);
+    assert!(line.copy_by_dashing(None, 0., &[]).is_none());
+
+    let moved = line.copy_by_transforming(&CGAffineTransform::new(1., 0., 0., 1., 0., 5.));
+    assert_eq!(moved.bounding_box().origin.y, 5.);
+}
+
+#[test]
+fn elements_test() {
+    let path = CGMutablePath::new();
+    path.move_to(None, 0., 0.);
+    path.add_line_to(None, 1., 0.);
+    path.add_quad_curve_to(None, 1., 1., 0., 1.);
+    path.add_curve_to(None, 0., 2., 1., 2., 1., 3.);
+    path.close_subpath();
+    let elements: Vec<_> = path.into_path().elements().collect();
+    assert_eq!(elements.len(), 5);
+    match elements[0] {
+        PathElement::MoveTo(point) => assert_eq!((point.x, point.y), (0., 0.)),
+        element => panic!("unexpected {:?}", element),
+    }
+    match elements[1] {
+        PathElement::LineTo(point) => assert_eq!((point.x, point.y), (1., 0.)),
+        element => panic!("unexpected {:?}", element),
+    }
+    match elements[2] {
+        PathElement::QuadCurveTo(control, point) => {
+            assert_eq!((control.x, control.y, point.x, point.y), (1., 1., 0., 1.))
+        }
+        element => panic!("unexpected {:?}", element),
+    }
+    match elements[3] {
+        PathElement::CurveTo(_, control, point) => {
+            assert_eq!((control.x, control.y, point.x, point.y), (1., 2., 1., 3.))
+        }
+        element => panic!("unexpected {:?}", element),
+    }
+    match elements[4] {
+        PathElement::Close => {}
+        element => panic!("unexpected {:?}", element),
+    }
+}
+
 #[link(name = "CoreGraphics", kind = "framework")]
 extern {
     fn CGPathApply(path: ::sys::CGPathRef, info: *mut c_void, function: CGPathApplierFunction);
     fn CGPathGetTypeID() -> CFTypeID;
+    fn CGPathCreateMutable() -> ::sys::CGPathRef;
+    fn CGPathCreateCopy(path: ::sys::CGPathRef) -> ::sys::CGPathRef;
+    fn CGPathCreateMutableCopy(path: ::sys::CGPathRef) -> ::sys::CGPathRef;
+    fn CGPathCreateCopyByStrokingPath(path: ::sys::CGPathRef, transform: *const CGAffineTransform,
+                                      lineWidth: CGFloat, lineCap: CGLineCap,
+                                      lineJoin: CGLineJoin, miterLimit: CGFloat)
+                                      -> ::sys::CGPathRef;
+    fn CGPathCreateCopyByDashingPath(path: ::sys::CGPathRef, transform: *const CGAffineTransform,
+                                     phase: CGFloat, lengths: *const CGFloat, count: size_t)
+                                     -> ::sys::CGPathRef;
+    fn CGPathCreateCopyByTransformingPath(path: ::sys::CGPathRef,
+                                          transform: *const CGAffineTransform)
+                                          -> ::sys::CGPathRef;
+    fn CGPathGetBoundingBox(path: ::sys::CGPathRef) -> CGRect;
+    fn CGPathGetPathBoundingBox(path: ::sys::CGPathRef) -> CGRect;
+    fn CGPathIsEmpty(path: ::sys::CGPathRef) -> bool;
+    fn CGPathIsRect(path: ::sys::CGPathRef, rect: *mut CGRect) -> bool;
+    fn CGPathEqualToPath(path1: ::sys::CGPathRef, path2: ::sys::CGPathRef) -> bool;
+    fn CGPathContainsPoint(path: ::sys::CGPathRef, m: *const CGAffineTransform,
+                           point: CGPoint, eoFill: bool) -> bool;
+    fn CGPathGetCurrentPoint(path: ::sys::CGPathRef) -> CGPoint;
+    fn CGPathMoveToPoint(path: ::sys::CGPathRef, m: *const CGAffineTransform,
+                         x: CGFloat, y: CGFloat);
+    fn CGPathAddLineToPoint(path: ::sys::CGPathRef, m: *const CGAffineTransform,
+                            x: CGFloat, y: CGFloat);
+    fn CGPathAddCurveToPoint(path: ::sys::CGPathRef, m: *const CGAffineTransform,
+                             cp1x: CGFloat, cp1y: CGFloat, cp2x: CGFloat, cp2y: CGFloat,
+                             x: CGFloat, y: CGFloat);
+    fn CGPathAddQuadCurveToPoint(path: ::sys::CGPathRef, m: *const CGAffineTransform,
+                                 cpx: CGFloat, cpy: CGFloat, x: CGFloat, y: CGFloat);
+    fn CGPathAddArc(path: ::sys::CGPathRef, m: *const CGAffineTransform,
+                    x: CGFloat, y: CGFloat, radius: CGFloat,
+                    startAngle: CGFloat, endAngle: CGFloat, clockwise: bool);
+    fn CGPathAddRect(path: ::sys::CGPathRef, m: *const CGAffineTransform, rect: CGRect);
+    fn CGPathAddEllipseInRect(path: ::sys::CGPathRef, m: *const CGAffineTransform, rect: CGRect);
+    fn CGPathAddRoundedRect(path: ::sys::CGPathRef, m: *const CGAffineTransform, rect: CGRect,
+                            cornerWidth: CGFloat, cornerHeight: CGFloat);
+    fn CGPathAddPath(path1: ::sys::CGPathRef, m: *const CGAffineTransform,
+                     path2: ::sys::CGPathRef);
+    fn CGPathCloseSubpath(path: ::sys::CGPathRef);
 }
@@ -0,0 +1,128 @@
+// Copyright 2018 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use base::CGFloat;
+use context::CGContext;
+use core_foundation::base::{CFRelease, CFRetain, CFTypeID};
+use foreign_types::ForeignType;
+use geometry::{CGAffineTransform, CGRect};
+use libc::{c_uint, c_void};
+use std::panic::{self, AssertUnwindSafe};
+
+/// How the cells of a pattern are fitted to device pixels.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CGPatternTiling {
+    /// Cells are spaced exactly, at the cost of distorting them by up to a pixel.
+    NoDistortion = 0,
+    /// Cells keep their shape, at the cost of spacing them by up to a pixel more or less.
+    ConstantSpacingMinimalDistortion = 1,
+    /// Cells are spaced exactly and keep their shape, which may be slower to draw.
+    ConstantSpacing = 2,
+}
+
+foreign_type! {
+    #[doc(hidden)]
+    type CType = ::sys::CGPattern;
+    fn drop = |p| CFRelease(p as *mut _);
+    fn clone = |p| CFRetain(p as *const _) as *mut _;
+    pub struct CGPattern;
+    pub struct CGPatternRef;
+}
+
+impl CGPattern {
+    pub fn type_id() -> CFTypeID {
+        unsafe {
+            CGPatternGetTypeID()
+        }
+    }
+
+    /// Creates a pattern that repeats the cell drawn by `draw` within `bounds`, every
+    /// `x_step` and `y_step` units of pattern space. `matrix` maps pattern space to the
+    /// default user space of the context the pattern is drawn into.
+    ///
+    /// A colored pattern draws its own colors. An uncolored one is a stencil that is painted
+    /// with the color given to `CGContext::set_fill_pattern`, and `draw` mustn't set colors.
+    ///
+    /// `draw` may be called any number of times, whenever the pattern is painted.
+    pub fn new<F>(bounds: CGRect,
+                  matrix: CGAffineTransform,
+                  x_step: CGFloat,
+                  y_step: CGFloat,
+                  tiling: CGPatternTiling,
+                  is_colored: bool,
+                  draw: F)
+                  -> CGPattern
+                  where F: Fn(&CGContext) + 'static {
+        let draw: Box<PatternDrawFn> = Box::new(Box::new(draw));
+        let callbacks = CGPatternCallbacks {
+            version: 0,
+            draw_pattern: draw_pattern,
+            release_info: release_info,
+        };
+        unsafe {
+            let result = CGPatternCreate(Box::into_raw(draw) as *mut c_void,
+                                         bounds,
+                                         matrix,
+                                         x_step,
+                                         y_step,
+                                         tiling,
+                                         is_colored,
+                                         &callbacks);
+            assert!(!result.is_null());
+            CGPattern::from_ptr(result)
+        }
+    }
+}
+
+// Boxed twice so that the pointer passed as the callback's info is thin.
+type PatternDrawFn = Box<Fn(&CGContext)>;
+
+unsafe extern "C" fn draw_pattern(info: *mut c_void, context: ::sys::CGContextRef) {
+    let draw = &*(info as *const PatternDrawFn);
+    let context = CGContext::from_ptr(CFRetain(context as *const _) as *mut _);
+    // Panics mustn't unwind into CoreGraphics; the cell is left as drawn so far.
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| draw(&context)));
+}
+
+unsafe extern "C" fn release_info(info: *mut c_void) {
+    drop(Box::from_raw(info as *mut PatternDrawFn));
+}
+
+#[repr(C)]
+struct CGPatternCallbacks {
+    version: c_uint,
+    draw_pattern: unsafe extern "C" fn(*mut c_void, ::sys::CGContextRef),
+    release_info: unsafe extern "C" fn(*mut c_void),
+}
+
+#[test]
+fn draw_pattern_panic_test() {
+    let draw: Box<PatternDrawFn> = Box::new(Box::new(|_: &CGContext| panic!("draw panicked")));
+    let info = Box::into_raw(draw) as *mut c_void;
+    let context = CGContext::create_argb_bitmap(4, 4);
+    unsafe {
+        draw_pattern(info, context.as_ptr());
+        release_info(info);
+    }
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern {
+    fn CGPatternGetTypeID() -> CFTypeID;
+    fn CGPatternCreate(info: *mut c_void,
+                       bounds: CGRect,
+                       matrix: CGAffineTransform,
+                       xStep: CGFloat,
+                       yStep: CGFloat,
+                       tiling: CGPatternTiling,
+                       isColored: bool,
+                       callbacks: *const CGPatternCallbacks)
+                       -> ::sys::CGPatternRef;
+}
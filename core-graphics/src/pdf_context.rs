@@ -0,0 +1,255 @@
+// Copyright 2018 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Contexts drawing into PDF documents.
+
+use context::CGContext;
+use core_foundation::base::{CFType, TCFType};
+use core_foundation::data::CFData;
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::string::{CFString, CFStringRef};
+use core_foundation::url::{CFURL, CFURLRef};
+use data_consumer::CGDataConsumer;
+use foreign_types::ForeignType;
+use geometry::CGRect;
+use std::mem;
+use std::ops::Deref;
+use std::ptr;
+use std::slice;
+
+/// A context drawing into a PDF document, one page at a time. All drawing
+/// is done through the `CGContext` it derefs to, between `begin_page` and
+/// `end_page` (or within `with_page`).
+pub struct CGPDFContext(CGContext);
+
+impl CGPDFContext {
+    /// Creates a PDF context that writes to the file at `url` once it is closed or dropped.
+    ///
+    /// `media_box` is the default page size; it is US Letter if `None`.
+    pub fn create_with_url(url: &CFURL, media_box: Option<CGRect>) -> Option<CGPDFContext> {
+        unsafe {
+            let media_box_ptr = media_box.as_ref().map_or(ptr::null(), |rect| rect as *const _);
+            let result = CGPDFContextCreateWithURL(url.as_concrete_TypeRef(),
+                                                   media_box_ptr,
+                                                   ptr::null());
+            if !result.is_null() {
+                Some(CGPDFContext(CGContext::from_ptr(result)))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Creates a PDF context that writes the document to `consumer`, e.g. one created with
+    /// `CGDataConsumer::from_buffer` to produce it in memory. The document is complete once
+    /// the context is closed or dropped.
+    ///
+    /// `media_box` is the default page size; it is US Letter if `None`.
+    pub fn create(consumer: &CGDataConsumer, media_box: Option<CGRect>)
+                  -> Option<CGPDFContext> {
+        unsafe {
+            let media_box_ptr = media_box.as_ref().map_or(ptr::null(), |rect| rect as *const _);
+            let result = CGPDFContextCreate(consumer.as_ptr(), media_box_ptr, ptr::null());
+            if !result.is_null() {
+                Some(CGPDFContext(CGContext::from_ptr(result)))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Starts a new page, sized to `media_box` or to the context's default media box if
+    /// `None`.
+    pub fn begin_page(&self, media_box: Option<CGRect>) {
+        unsafe {
+            let media_box_ptr = media_box.as_ref().map_or(ptr::null(), |rect| rect as *const _);
+            CGContextBeginPage(self.as_ptr(), media_box_ptr)
+        }
+    }
+
+    /// Starts a new page with the given media box and, optionally, a crop box limiting the
+    /// visible part of the page.
+    pub fn begin_page_with_boxes(&self, media_box: CGRect, crop_box: Option<CGRect>) {
+        fn rect_data(rect: &CGRect) -> CFType {
+            unsafe {
+                let bytes = slice::from_raw_parts(rect as *const CGRect as *const u8,
+                                                  mem::size_of::<CGRect>());
+                CFData::from_buffer(bytes).as_CFType()
+            }
+        }
+
+        unsafe {
+            let mut pairs = vec![
+                (CFString::wrap_under_get_rule(kCGPDFContextMediaBox), rect_data(&media_box)),
+            ];
+            if let Some(ref crop_box) = crop_box {
+                pairs.push((CFString::wrap_under_get_rule(kCGPDFContextCropBox),
+                            rect_data(crop_box)));
+            }
+            let page_info = CFDictionary::from_CFType_pairs(&pairs);
+            CGPDFContextBeginPage(self.as_ptr(), page_info.as_concrete_TypeRef())
+        }
+    }
+
+    pub fn end_page(&self) {
+        unsafe {
+            CGContextEndPage(self.as_ptr())
+        }
+    }
+
+    /// Draws one page with `f`, ending the page when `f` returns or panics.
+    pub fn with_page<R, F>(&self, media_box: Option<CGRect>, f: F) -> R
+                           where F: FnOnce(&CGContext) -> R {
+        self.0.with_pdf_page(media_box, f)
+    }
+
+    /// Closes the context, flushing the document. No drawing may be done afterwards.
+    pub fn close(&self) {
+        unsafe {
+            CGPDFContextClose(self.as_ptr())
+        }
+    }
+}
+
+impl CGContext {
+    /// Draws one page of a PDF context with `f`, sized to `media_box` or to the context's
+    /// default media box if `None`, ending the page when `f` returns or panics.
+    ///
+    /// This is for PDF contexts that are only available as a `CGContext`, e.g. one handed out
+    /// by a print system; it has no effect on other contexts.
+    pub fn with_pdf_page<R, F>(&self, media_box: Option<CGRect>, f: F) -> R
+                               where F: FnOnce(&CGContext) -> R {
+        struct PageGuard<'a>(&'a CGContext);
+
+        impl<'a> Drop for PageGuard<'a> {
+            fn drop(&mut self) {
+                unsafe {
+                    CGContextEndPage(self.0.as_ptr())
+                }
+            }
+        }
+
+        unsafe {
+            let media_box_ptr = media_box.as_ref().map_or(ptr::null(), |rect| rect as *const _);
+            CGContextBeginPage(self.as_ptr(), media_box_ptr)
+        }
+        let _guard = PageGuard(self);
+        f(self)
+    }
+}
+
+impl Deref for CGPDFContext {
+    type Target = CGContext;
+
+    fn deref(&self) -> &CGContext {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+fn page_count(url: &CFURL) -> usize {
+    use core_foundation::base::CFRelease;
+    use libc::{c_void, size_t};
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern {
+        fn CGPDFDocumentCreateWithURL(url: CFURLRef) -> *mut c_void;
+        fn CGPDFDocumentGetNumberOfPages(document: *mut c_void) -> size_t;
+    }
+
+    unsafe {
+        let document = CGPDFDocumentCreateWithURL(url.as_concrete_TypeRef());
+        assert!(!document.is_null());
+        let count = CGPDFDocumentGetNumberOfPages(document);
+        CFRelease(document as *mut _);
+        count
+    }
+}
+
+#[test]
+fn with_page_test() {
+    use geometry::*;
+
+    let path = ::std::env::temp_dir().join("core-graphics-with-page-test.pdf");
+    let url = CFURL::from_path(&path, false).unwrap();
+    let media_box = CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(100., 100.));
+    let ctx = CGPDFContext::create_with_url(&url, Some(media_box)).unwrap();
+    for _ in 0..2 {
+        ctx.with_page(None, |ctx| ctx.fill_rect(media_box));
+    }
+    ctx.close();
+    drop(ctx);
+
+    assert_eq!(2, page_count(&url));
+    let _ = ::std::fs::remove_file(path);
+}
+
+#[test]
+fn with_pdf_page_test() {
+    use geometry::*;
+
+    fn draw_pages(ctx: &CGContext, media_box: CGRect) {
+        for _ in 0..2 {
+            ctx.with_pdf_page(None, |ctx| ctx.fill_rect(media_box));
+        }
+    }
+
+    let path = ::std::env::temp_dir().join("core-graphics-with-pdf-page-test.pdf");
+    let url = CFURL::from_path(&path, false).unwrap();
+    let media_box = CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(100., 100.));
+    let ctx = CGPDFContext::create_with_url(&url, Some(media_box)).unwrap();
+    draw_pages(&ctx, media_box);
+    ctx.close();
+    drop(ctx);
+
+    assert_eq!(2, page_count(&url));
+    let _ = ::std::fs::remove_file(path);
+}
+
+#[test]
+fn create_test() {
+    use context::CGContextType;
+    use geometry::*;
+    use std::sync::{Arc, Mutex};
+
+    let buffer = Arc::new(Mutex::new(vec![]));
+    let consumer = CGDataConsumer::from_buffer(buffer.clone());
+    let ctx = CGPDFContext::create(&consumer, None).unwrap();
+    assert_eq!(ctx.context_type(), CGContextType::PDF);
+    let media_box = CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(100., 100.));
+    let crop_box = CGRect::new(&CGPoint::new(10., 10.), &CGSize::new(50., 50.));
+    ctx.begin_page_with_boxes(media_box, Some(crop_box));
+    ctx.fill_rect(media_box);
+    ctx.end_page();
+    ctx.close();
+    drop(ctx);
+    drop(consumer);
+
+    let buffer = buffer.lock().unwrap();
+    assert!(buffer.starts_with(b"%PDF"));
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern {
+    fn CGContextBeginPage(c: ::sys::CGContextRef, mediaBox: *const CGRect);
+    fn CGContextEndPage(c: ::sys::CGContextRef);
+    fn CGPDFContextCreateWithURL(url: CFURLRef,
+                                 mediaBox: *const CGRect,
+                                 auxiliaryInfo: CFDictionaryRef)
+                                 -> ::sys::CGContextRef;
+    fn CGPDFContextCreate(consumer: ::sys::CGDataConsumerRef,
+                          mediaBox: *const CGRect,
+                          auxiliaryInfo: CFDictionaryRef)
+                          -> ::sys::CGContextRef;
+    fn CGPDFContextBeginPage(c: ::sys::CGContextRef, pageInfo: CFDictionaryRef);
+    fn CGPDFContextClose(c: ::sys::CGContextRef);
+
+    static kCGPDFContextMediaBox: CFStringRef;
+    static kCGPDFContextCropBox: CFStringRef;
+}
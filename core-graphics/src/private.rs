@@ -11,8 +11,14 @@
 //!
 //! These are liable to change at any time. Use with caution!
 
-use geometry::CGRect;
-use libc::{c_int, c_uint};
+use base::{kCGBitmapByteOrder32Big, kCGImageAlphaPremultipliedFirst};
+use color_space::CGColorSpace;
+use context::CGContext;
+use display_stream::IOSurfaceRef;
+use foreign_types::ForeignType;
+use geometry::{CGPoint, CGRect, CGSize};
+use image::CGImage;
+use libc::{c_int, c_uint, c_void};
 use std::ptr;
 
 pub struct CGSRegion {
@@ -86,9 +92,142 @@ impl CGSSurface {
     }
 }
 
+/// The image of the mouse cursor currently shown by the window server.
+pub struct CGSCursor {
+    /// The cursor image, at the resolution of the display it is on.
+    pub image: CGImage,
+    /// The point within the cursor, in points from its top-left corner, that
+    /// is placed at the mouse location.
+    pub hot_spot: CGPoint,
+    /// The size of the cursor in points.
+    pub size: CGSize,
+}
+
+impl CGSCursor {
+    /// Returns the current cursor, or `None` if the window server doesn't
+    /// provide it.
+    pub fn current() -> Option<CGSCursor> {
+        unsafe {
+            let connection = ffi::CGSMainConnectionID();
+            let mut size = 0;
+            if ffi::CGSGetGlobalCursorDataSize(connection, &mut size) != 0 || size <= 0 {
+                return None;
+            }
+
+            let mut data = vec![0u8; size as usize];
+            let (mut row_bytes, mut depth, mut components, mut bits_per_component) = (0, 0, 0, 0);
+            let mut rect = CGRect::default();
+            let mut hot_spot = CGPoint::default();
+            if ffi::CGSGetGlobalCursorData(connection,
+                                           data.as_mut_ptr(),
+                                           &mut size,
+                                           &mut row_bytes,
+                                           &mut rect,
+                                           &mut hot_spot,
+                                           &mut depth,
+                                           &mut components,
+                                           &mut bits_per_component) != 0 {
+                return None;
+            }
+            if bits_per_component != 8 || components != 4 || row_bytes <= 0 {
+                return None;
+            }
+
+            // The cursor is 32-bit premultiplied ARGB; copy it out through a bitmap context.
+            let width = row_bytes as usize / 4;
+            let height = size as usize / row_bytes as usize;
+            let context = CGContext::create_bitmap_context(
+                Some(data.as_mut_ptr() as *mut c_void),
+                width, height, 8, row_bytes as usize,
+                &CGColorSpace::create_device_rgb(),
+                kCGImageAlphaPremultipliedFirst | kCGBitmapByteOrder32Big);
+            context.create_image().map(|image| {
+                CGSCursor {
+                    image: image,
+                    hot_spot: hot_spot,
+                    size: rect.size,
+                }
+            })
+        }
+    }
+}
+
+/// Creates a context drawing directly into `surface`, e.g. one shared with another process
+/// or used as a GPU texture, without copying through a bitmap.
+///
+/// The context's type is `CGContextType::IOSurface`; drawing must be flushed with
+/// `CGContext::flush` before the surface is used elsewhere.
+///
+/// # Safety
+///
+/// `surface` must be a valid `IOSurface`, and `width`, `height`, `bits_per_component`,
+/// `bits_per_pixel`, `space` and `bitmap_info` must describe its pixels: the context writes
+/// into the surface's memory according to them without checking.
+pub unsafe fn create_io_surface_context(surface: IOSurfaceRef,
+                                        width: usize,
+                                        height: usize,
+                                        bits_per_component: usize,
+                                        bits_per_pixel: usize,
+                                        space: &CGColorSpace,
+                                        bitmap_info: u32)
+                                        -> Option<CGContext> {
+    let result = ffi::CGIOSurfaceContextCreate(surface,
+                                               width,
+                                               height,
+                                               bits_per_component,
+                                               bits_per_pixel,
+                                               space.as_ptr(),
+                                               bitmap_info);
+    if !result.is_null() {
+        Some(CGContext::from_ptr(result))
+    } else {
+        None
+    }
+}
+
+#[test]
+fn create_io_surface_context_test() {
+    use base::kCGBitmapByteOrder32Little;
+    use context::CGContextType;
+    use core_foundation::base::{CFRelease, TCFType};
+    use core_foundation::dictionary::CFDictionary;
+    use core_foundation::number::CFNumber;
+    use core_foundation::string::CFString;
+    use display_stream::kCVPixelFormatType_32BGRA;
+
+    let properties = CFDictionary::from_CFType_pairs(&[
+        (CFString::from_static_string("IOSurfaceWidth"), CFNumber::from(4)),
+        (CFString::from_static_string("IOSurfaceHeight"), CFNumber::from(2)),
+        (CFString::from_static_string("IOSurfaceBytesPerElement"), CFNumber::from(4)),
+        (CFString::from_static_string("IOSurfacePixelFormat"),
+         CFNumber::from(kCVPixelFormatType_32BGRA)),
+    ]);
+    unsafe {
+        let surface = ffi::IOSurfaceCreate(properties.as_concrete_TypeRef());
+        assert!(!surface.is_null());
+        let context = create_io_surface_context(surface, 4, 2, 8, 32,
+                                                &CGColorSpace::create_device_rgb(),
+                                                kCGImageAlphaPremultipliedFirst |
+                                                kCGBitmapByteOrder32Little).unwrap();
+        assert_eq!(context.context_type(), CGContextType::IOSurface);
+        context.set_rgb_fill_color(1., 0., 0., 1.);
+        context.fill_rect(CGRect::new(&CGPoint::new(0., 0.), &CGSize::new(4., 2.)));
+        context.flush();
+
+        ffi::IOSurfaceLock(surface, ffi::kIOSurfaceLockReadOnly, ptr::null_mut());
+        let pixel = ffi::IOSurfaceGetBaseAddress(surface) as *const [u8; 4];
+        assert_eq!(*pixel, [0, 0, 255, 255]);
+        ffi::IOSurfaceUnlock(surface, ffi::kIOSurfaceLockReadOnly, ptr::null_mut());
+
+        drop(context);
+        CFRelease(surface as *const _);
+    }
+}
+
 mod ffi {
-    use geometry::CGRect;
-    use libc::{c_int, c_uint};
+    use display_stream::IOSurfaceRef;
+    use geometry::{CGPoint, CGRect};
+    use libc::{c_int, c_uint, size_t};
 
     // This is an enum so that we can't easily make instances of this opaque type.
     pub enum CGSRegionObject {}
@@ -97,8 +236,23 @@ mod ffi {
     pub type CGSRegionRef = *mut CGSRegionObject;
     pub type OSStatus = i32;
 
+    pub type CGSConnectionID = c_int;
+
     #[link(name = "CoreGraphics", kind = "framework")]
     extern {
+        pub fn CGSMainConnectionID() -> CGSConnectionID;
+        pub fn CGSGetGlobalCursorDataSize(connection: CGSConnectionID, size: *mut c_int) -> CGError;
+        pub fn CGSGetGlobalCursorData(connection: CGSConnectionID,
+                                      data: *mut u8,
+                                      size: *mut c_int,
+                                      rowBytes: *mut c_int,
+                                      rect: *mut CGRect,
+                                      hotSpot: *mut CGPoint,
+                                      depth: *mut c_int,
+                                      components: *mut c_int,
+                                      bitsPerComponent: *mut c_int)
+                                      -> CGError;
+
         pub fn CGSRegionRelease(region: CGSRegionRef);
         pub fn CGSNewRegionWithRect(rect: *const CGRect, outRegion: *mut CGSRegionRef) -> CGError;
         pub fn CGSNewRegionWithRectList(rects: *const CGRect,
@@ -106,11 +260,35 @@ mod ffi {
                                         outRegion: *mut CGSRegionRef)
                                         -> CGError;
 
+        pub fn CGIOSurfaceContextCreate(surface: IOSurfaceRef,
+                                        width: size_t,
+                                        height: size_t,
+                                        bitsPerComponent: size_t,
+                                        bitsPerPixel: size_t,
+                                        space: ::sys::CGColorSpaceRef,
+                                        bitmapInfo: u32)
+                                        -> ::sys::CGContextRef;
+
         pub fn CGSSetSurfaceShape(contextID: c_uint,
                                   windowNumber: c_int,
                                   surfaceID: c_uint,
                                   region: CGSRegionRef)
                                   -> CGError;
     }
+
+    #[cfg(test)]
+    #[allow(non_upper_case_globals)]
+    pub const kIOSurfaceLockReadOnly: u32 = 1;
+
+    #[cfg(test)]
+    #[link(name = "IOSurface", kind = "framework")]
+    extern {
+        pub fn IOSurfaceCreate(properties: ::core_foundation::dictionary::CFDictionaryRef)
+                               -> IOSurfaceRef;
+        pub fn IOSurfaceLock(buffer: IOSurfaceRef, options: u32, seed: *mut u32) -> i32;
+        pub fn IOSurfaceUnlock(buffer: IOSurfaceRef, options: u32, seed: *mut u32) -> i32;
+        pub fn IOSurfaceGetBaseAddress(buffer: IOSurfaceRef) -> *mut ::libc::c_void;
+    }
 }
 
+
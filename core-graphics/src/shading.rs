@@ -0,0 +1,192 @@
+// Copyright 2018 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use base::CGFloat;
+use color_space::CGColorSpace;
+use core_foundation::base::{CFRelease, CFRetain, CFTypeID};
+use foreign_types::ForeignType;
+use geometry::CGPoint;
+use libc::{c_uint, c_void, size_t};
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+foreign_type! {
+    #[doc(hidden)]
+    type CType = ::sys::CGShading;
+    fn drop = |p| CFRelease(p as *mut _);
+    fn clone = |p| CFRetain(p as *const _) as *mut _;
+    pub struct CGShading;
+    pub struct CGShadingRef;
+}
+
+impl CGShading {
+    pub fn type_id() -> CFTypeID {
+        unsafe {
+            CGShadingGetTypeID()
+        }
+    }
+
+    /// Creates a shading that varies along the line from `start` to `end`, optionally
+    /// extended beyond either of them.
+    ///
+    /// `function` is given a position from 0 at `start` to 1 at `end` and fills in the
+    /// components of the color in `space` there, followed by its alpha.
+    pub fn new_axial<F>(space: &CGColorSpace,
+                        start: CGPoint,
+                        end: CGPoint,
+                        extend: (bool, bool),
+                        function: F)
+                        -> CGShading
+                        where F: Fn(CGFloat, &mut [CGFloat]) + 'static {
+        let function = create_function(space, function);
+        unsafe {
+            let result = CGShadingCreateAxial(space.as_ptr(),
+                                              start,
+                                              end,
+                                              function,
+                                              extend.0,
+                                              extend.1);
+            CFRelease(function as *mut _);
+            assert!(!result.is_null());
+            CGShading::from_ptr(result)
+        }
+    }
+
+    /// Creates a shading that varies between the circle around `start` of `start_radius` and
+    /// the one around `end` of `end_radius`, like `new_axial`.
+    pub fn new_radial<F>(space: &CGColorSpace,
+                         start: CGPoint,
+                         start_radius: CGFloat,
+                         end: CGPoint,
+                         end_radius: CGFloat,
+                         extend: (bool, bool),
+                         function: F)
+                         -> CGShading
+                         where F: Fn(CGFloat, &mut [CGFloat]) + 'static {
+        let function = create_function(space, function);
+        unsafe {
+            let result = CGShadingCreateRadial(space.as_ptr(),
+                                               start,
+                                               start_radius,
+                                               end,
+                                               end_radius,
+                                               function,
+                                               extend.0,
+                                               extend.1);
+            CFRelease(function as *mut _);
+            assert!(!result.is_null());
+            CGShading::from_ptr(result)
+        }
+    }
+}
+
+type ShadingFunction = Box<Fn(CGFloat, &mut [CGFloat])>;
+
+// The closure and the number of values it produces.
+struct FunctionInfo {
+    function: ShadingFunction,
+    range_dimension: usize,
+}
+
+// Wraps `function` in a `CGFunction` from [0, 1] to the colors of `space`.
+fn create_function<F>(space: &CGColorSpace, function: F) -> CGFunctionRef
+                      where F: Fn(CGFloat, &mut [CGFloat]) + 'static {
+    let range_dimension = space.number_of_components() + 1;
+    let domain = [0., 1.];
+    let range: Vec<CGFloat> = (0..range_dimension).flat_map(|_| vec![0., 1.]).collect();
+    let info = Box::new(FunctionInfo {
+        function: Box::new(function),
+        range_dimension: range_dimension,
+    });
+    let callbacks = CGFunctionCallbacks {
+        version: 0,
+        evaluate: evaluate,
+        release_info: release_info,
+    };
+    unsafe {
+        let result = CGFunctionCreate(Box::into_raw(info) as *mut c_void,
+                                      1,
+                                      domain.as_ptr(),
+                                      range_dimension,
+                                      range.as_ptr(),
+                                      &callbacks);
+        assert!(!result.is_null());
+        result
+    }
+}
+
+unsafe extern "C" fn evaluate(info: *mut c_void, input: *const CGFloat, output: *mut CGFloat) {
+    let info = &*(info as *const FunctionInfo);
+    let output = slice::from_raw_parts_mut(output, info.range_dimension);
+    // Panics mustn't unwind into CoreGraphics.
+    if panic::catch_unwind(AssertUnwindSafe(|| (info.function)(*input, output))).is_err() {
+        for value in output.iter_mut() {
+            *value = 0.;
+        }
+    }
+}
+
+unsafe extern "C" fn release_info(info: *mut c_void) {
+    drop(Box::from_raw(info as *mut FunctionInfo));
+}
+
+enum CGFunction {}
+type CGFunctionRef = *mut CGFunction;
+
+#[repr(C)]
+struct CGFunctionCallbacks {
+    version: c_uint,
+    evaluate: unsafe extern "C" fn(*mut c_void, *const CGFloat, *mut CGFloat),
+    release_info: unsafe extern "C" fn(*mut c_void),
+}
+
+#[test]
+fn evaluate_panic_test() {
+    let info = FunctionInfo {
+        function: Box::new(|t, output: &mut [CGFloat]| {
+            output[0] = t;
+            panic!("evaluate panicked")
+        }),
+        range_dimension: 2,
+    };
+    let input = 0.5;
+    let mut output = [1., 1.];
+    unsafe {
+        evaluate(&info as *const FunctionInfo as *mut c_void, &input, output.as_mut_ptr());
+    }
+    assert_eq!(output, [0., 0.]);
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern {
+    fn CGShadingGetTypeID() -> CFTypeID;
+    fn CGShadingCreateAxial(space: ::sys::CGColorSpaceRef,
+                            start: CGPoint,
+                            end: CGPoint,
+                            function: CGFunctionRef,
+                            extendStart: bool,
+                            extendEnd: bool)
+                            -> ::sys::CGShadingRef;
+    fn CGShadingCreateRadial(space: ::sys::CGColorSpaceRef,
+                             start: CGPoint,
+                             startRadius: CGFloat,
+                             end: CGPoint,
+                             endRadius: CGFloat,
+                             function: CGFunctionRef,
+                             extendStart: bool,
+                             extendEnd: bool)
+                             -> ::sys::CGShadingRef;
+    fn CGFunctionCreate(info: *mut c_void,
+                        domainDimension: size_t,
+                        domain: *const CGFloat,
+                        rangeDimension: size_t,
+                        range: *const CGFloat,
+                        callbacks: *const CGFunctionCallbacks)
+                        -> CGFunctionRef;
+}
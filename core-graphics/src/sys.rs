@@ -1,12 +1,18 @@
 pub enum CGImage {}
 pub type CGImageRef = *mut CGImage;
 
+pub enum CGColor {}
+pub type CGColorRef = *mut CGColor;
+
 pub enum CGColorSpace {}
 pub type CGColorSpaceRef = *mut CGColorSpace;
 
 pub enum CGPath {}
 pub type CGPathRef = *mut CGPath;
 
+pub enum CGDataConsumer {}
+pub type CGDataConsumerRef = *mut CGDataConsumer;
+
 pub enum CGDataProvider {}
 pub type CGDataProviderRef = *mut CGDataProvider;
 
@@ -16,6 +22,24 @@ pub type CGFontRef = *mut CGFont;
 pub enum CGContext {}
 pub type CGContextRef = *mut CGContext;
 
+pub enum CGGradient {}
+pub type CGGradientRef = *mut CGGradient;
+
+pub enum CGPattern {}
+pub type CGPatternRef = *mut CGPattern;
+
+pub enum CGShading {}
+pub type CGShadingRef = *mut CGShading;
+
+pub enum CGLayer {}
+pub type CGLayerRef = *mut CGLayer;
+
+pub enum CGImageSource {}
+pub type CGImageSourceRef = *mut CGImageSource;
+
+pub enum CGImageDestination {}
+pub type CGImageDestinationRef = *mut CGImageDestination;
+
 #[cfg(target_os = "macos")]
 mod macos {
 	pub enum CGEvent {}
@@ -26,6 +50,12 @@ mod macos {
 
 	pub enum CGDisplayMode {}
 	pub type CGDisplayModeRef = *mut CGDisplayMode;
+
+	pub enum CGDisplayStream {}
+	pub type CGDisplayStreamRef = *mut CGDisplayStream;
+
+	pub enum CGDisplayStreamUpdate {}
+	pub type CGDisplayStreamUpdateRef = *mut CGDisplayStreamUpdate;
 }
 
 #[cfg(target_os = "macos")]